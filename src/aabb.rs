@@ -0,0 +1,67 @@
+use std::mem::swap;
+
+use crate::{ray::Ray, vector::Vec3};
+
+// Axis aligned bounding box
+#[derive(Clone, Copy, Debug)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub fn new(min: Vec3, max: Vec3) -> Self {
+        Aabb { min, max }
+    }
+
+    pub fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> bool {
+        let (mut t_min, mut t_max) = (t_min, t_max);
+
+        for axis in 0..3 {
+            let inv_d = 1.0 / ray.dir[axis];
+            let mut t0 = (self.min[axis] - ray.origin[axis]) * inv_d;
+            let mut t1 = (self.max[axis] - ray.origin[axis]) * inv_d;
+            if inv_d < 0.0 {
+                swap(&mut t0, &mut t1);
+            }
+            // Written so a NaN from `0.0 * inf` leaves the interval unchanged
+            t_min = if t0 > t_min { t0 } else { t_min };
+            t_max = if t1 < t_max { t1 } else { t_max };
+            if t_max < t_min {
+                return false;
+            }
+        }
+        true
+    }
+
+    pub fn centroid(&self) -> Vec3 {
+        0.5 * (self.min + self.max)
+    }
+
+    // Return the index of the axis along which the box is longest
+    pub fn longest_axis(&self) -> usize {
+        let extent = self.max - self.min;
+        if extent.x > extent.y && extent.x > extent.z {
+            0
+        } else if extent.y > extent.z {
+            1
+        } else {
+            2
+        }
+    }
+}
+
+// Return the smallest box enclosing both `a` and `b`
+pub fn surrounding_box(a: &Aabb, b: &Aabb) -> Aabb {
+    let min = Vec3::new(
+        a.min.x.min(b.min.x),
+        a.min.y.min(b.min.y),
+        a.min.z.min(b.min.z),
+    );
+    let max = Vec3::new(
+        a.max.x.max(b.max.x),
+        a.max.y.max(b.max.y),
+        a.max.z.max(b.max.z),
+    );
+    Aabb::new(min, max)
+}
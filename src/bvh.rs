@@ -0,0 +1,176 @@
+use crate::{
+    aabb::{surrounding_box, Aabb},
+    ray::{Ray, RayHit},
+    shapes::Hittable,
+};
+
+// Bounding volume hierarchy built over a list of objects. Objects without a
+// bounding box can't be placed in the tree so they are tested separately.
+pub struct Bvh {
+    root: Option<BvhNode>,
+    unbounded: Vec<Box<dyn Hittable>>,
+}
+
+enum BvhNode {
+    Leaf {
+        object: Box<dyn Hittable>,
+        bbox: Aabb,
+    },
+    Branch {
+        left: Box<BvhNode>,
+        right: Box<BvhNode>,
+        bbox: Aabb,
+    },
+}
+
+impl Bvh {
+    pub fn new(objects: Vec<Box<dyn Hittable>>, time_0: f32, time_1: f32) -> Self {
+        let mut bounded = Vec::with_capacity(objects.len());
+        let mut unbounded = Vec::new();
+
+        for object in objects {
+            match object.bounding_box(time_0, time_1) {
+                Some(bbox) => bounded.push((bbox, object)),
+                None => unbounded.push(object),
+            }
+        }
+
+        let root = if bounded.is_empty() {
+            None
+        } else {
+            Some(BvhNode::build(bounded))
+        };
+
+        Bvh { root, unbounded }
+    }
+}
+
+impl BvhNode {
+    fn build(mut objects: Vec<(Aabb, Box<dyn Hittable>)>) -> Self {
+        if objects.len() == 1 {
+            let (bbox, object) = objects.pop().unwrap();
+            return BvhNode::Leaf { object, bbox };
+        }
+
+        // Split at the median centroid along the axis the centroids are most
+        // spread out on
+        let first = objects[0].0.centroid();
+        let centroid_bounds = objects.iter().fold(Aabb::new(first, first), |acc, obj| {
+            let c = obj.0.centroid();
+            surrounding_box(&acc, &Aabb::new(c, c))
+        });
+        let axis = centroid_bounds.longest_axis();
+        objects.sort_by(|a, b| a.0.centroid()[axis].total_cmp(&b.0.centroid()[axis]));
+
+        let right_objects = objects.split_off(objects.len() / 2);
+        let left = BvhNode::build(objects);
+        let right = BvhNode::build(right_objects);
+        let bbox = surrounding_box(left.bbox(), right.bbox());
+
+        BvhNode::Branch {
+            left: Box::new(left),
+            right: Box::new(right),
+            bbox,
+        }
+    }
+
+    fn bbox(&self) -> &Aabb {
+        match self {
+            BvhNode::Leaf { bbox, .. } => bbox,
+            BvhNode::Branch { bbox, .. } => bbox,
+        }
+    }
+
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<RayHit> {
+        if !self.bbox().hit(ray, t_min, t_max) {
+            return None;
+        }
+
+        match self {
+            BvhNode::Leaf { object, .. } => object.hit(ray, t_min, t_max),
+            BvhNode::Branch { left, right, .. } => {
+                let left_hit = left.hit(ray, t_min, t_max);
+                // Only look for hits on the right that are closer than the left
+                let t_max = left_hit.as_ref().map_or(t_max, |hit| hit.t);
+                right.hit(ray, t_min, t_max).or(left_hit)
+            }
+        }
+    }
+}
+
+impl Hittable for Bvh {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<RayHit> {
+        let mut hit = self
+            .root
+            .as_ref()
+            .and_then(|root| root.hit(ray, t_min, t_max));
+
+        for object in self.unbounded.iter() {
+            let closest = hit.as_ref().map_or(t_max, |hit| hit.t);
+            if let Some(object_hit) = object.hit(ray, t_min, closest) {
+                hit = Some(object_hit);
+            }
+        }
+
+        hit
+    }
+
+    fn bounding_box(&self, _time_0: f32, _time_1: f32) -> Option<Aabb> {
+        if !self.unbounded.is_empty() {
+            return None;
+        }
+        self.root.as_ref().map(|root| *root.bbox())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        bvh::Bvh,
+        material::Lambertian,
+        ray::Ray,
+        shapes::{Hittable, MSphere, Sphere},
+        vector::Vec3,
+    };
+
+    fn spheres() -> Vec<Box<dyn Hittable>> {
+        let mat = Arc::new(Lambertian::default());
+        let mut objects: Vec<Box<dyn Hittable>> = Vec::new();
+        for i in 0..10 {
+            for j in 0..10 {
+                let center = Vec3::new(i as f32 - 5.0, (i * j % 7) as f32 * 0.1, j as f32 - 5.0);
+                objects.push(Box::new(Sphere::new(center, 0.3, mat.clone())));
+            }
+        }
+        objects.push(Box::new(MSphere::new(
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, 2.0, 0.0),
+            0.5,
+            0.0,
+            1.0,
+            mat,
+        )));
+        objects
+    }
+
+    #[test]
+    fn matches_linear_scan() {
+        let list = spheres();
+        let bvh = Bvh::new(spheres(), 0.0, 1.0);
+
+        let origin = Vec3::new(0.5, 6.0, 9.0);
+        for i in 0..40 {
+            for j in 0..40 {
+                let target = Vec3::new(i as f32 / 4.0 - 5.0, 0.0, j as f32 / 4.0 - 5.0);
+                let time = ((i + j) % 5) as f32 / 4.0;
+                let ray = Ray::new(origin, target - origin, time);
+
+                let expected = list.hit(&ray, 0.001, f32::MAX).map(|hit| hit.t);
+                let actual = bvh.hit(&ray, 0.001, f32::MAX).map(|hit| hit.t);
+                assert_eq!(expected, actual);
+            }
+        }
+    }
+}
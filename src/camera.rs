@@ -2,7 +2,8 @@ use std::f32::consts::PI;
 
 use crate::{
     ray::Ray,
-    utils::{gen_random, random_in_unit_disk},
+    sampler::Sampler,
+    utils::random_in_unit_disk,
     vector::{cross, Vec3},
 };
 
@@ -19,6 +20,7 @@ pub struct Camera {
 }
 
 impl Camera {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         look_from: Vec3,
         look_at: Vec3,
@@ -58,13 +60,25 @@ impl Camera {
         }
     }
 
-    pub fn get_ray(&self, s: f32, t: f32) -> Ray {
-        let rd = self.lens_radius * random_in_unit_disk();
+    // The shutter open and close times
+    pub fn time_interval(&self) -> (f32, f32) {
+        (self.time_0, self.time_1)
+    }
+
+    // Width of a pixel at the focus plane for an image `height` pixels tall.
+    // Rays reach the focus plane at `t = 1` so this is also how quickly a
+    // pixel's footprint grows with `t`.
+    pub fn pixel_spread(&self, height: u64) -> f32 {
+        self.vertical.get_mag() / height as f32
+    }
+
+    pub fn get_ray(&self, s: f32, t: f32, sampler: &mut dyn Sampler) -> Ray {
+        let rd = self.lens_radius * random_in_unit_disk(sampler);
         let offset = self.u * rd.x + self.v * rd.y;
         let dir =
             self.lower_left + (s * self.horizontal) + (t * self.vertical) - self.origin - offset;
 
-        let time = self.time_0 + gen_random() * (self.time_1 - self.time_0);
+        let time = self.time_0 + sampler.get_1d() * (self.time_1 - self.time_0);
         Ray::new(self.origin + offset, dir, time)
     }
 }
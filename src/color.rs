@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+
+use crate::vector::Vec3;
+
+// Everything is rendered in linear light. Colours only get the sRGB transfer
+// function applied on the way out to 8 bit images, and 8 bit textures have it
+// taken off again when they're loaded.
+
+// How the values in an 8 bit image should be read
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorSpace {
+    // Colour images, almost every photo or painted texture
+    #[default]
+    Srgb,
+    // Non-colour data like roughness or normal maps, used as is
+    Linear,
+}
+
+impl ColorSpace {
+    // Convert an 8 bit value to a linear one between 0 and 1
+    pub fn decode(self, val: u8) -> f32 {
+        let val = val as f32 / 255.0;
+        match self {
+            ColorSpace::Srgb => srgb_to_linear(val),
+            ColorSpace::Linear => val,
+        }
+    }
+}
+
+// Perceived brightness of a linear colour, using the Rec. 709 weights
+pub fn luminance(col: Vec3) -> f32 {
+    0.2126 * col.x + 0.7152 * col.y + 0.0722 * col.z
+}
+
+// Convert a CIE XYZ colour to linear sRGB, which shares its white point D65.
+// Colours outside the sRGB gamut come out with negative channels.
+pub fn xyz_to_linear_srgb(xyz: Vec3) -> Vec3 {
+    Vec3::new(
+        3.2406 * xyz.x - 1.5372 * xyz.y - 0.4986 * xyz.z,
+        -0.9689 * xyz.x + 1.8758 * xyz.y + 0.0415 * xyz.z,
+        0.0557 * xyz.x - 0.2040 * xyz.y + 1.0570 * xyz.z,
+    )
+}
+
+pub fn srgb_to_linear(val: f32) -> f32 {
+    if val <= 0.04045 {
+        val / 12.92
+    } else {
+        ((val + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub fn linear_to_srgb(val: f32) -> f32 {
+    if val <= 0.0031308 {
+        val * 12.92
+    } else {
+        1.055 * val.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::color::{linear_to_srgb, srgb_to_linear, ColorSpace};
+
+    #[test]
+    fn srgb_transfer() {
+        // Middle grey in sRGB is a bit over a fifth of the light
+        assert!((srgb_to_linear(0.5) - 0.214).abs() < 1e-3);
+        assert_eq!(ColorSpace::Srgb.decode(255), 1.0);
+        assert_eq!(ColorSpace::Linear.decode(51), 0.2);
+
+        for i in 0..=255 {
+            let val = ColorSpace::Srgb.decode(i);
+            let encoded = (linear_to_srgb(val) * 255.0).round() as u8;
+            assert_eq!(encoded, i);
+        }
+    }
+}
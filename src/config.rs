@@ -1,6 +1,67 @@
-use clap::{App, Arg};
+use clap::{App, Arg, ArgMatches, Error, ErrorKind};
 
-pub fn get_config() -> (u64, u64, String) {
+use std::{
+    cmp::Ordering,
+    path::{Path, PathBuf},
+    str::FromStr,
+    thread::available_parallelism,
+};
+
+use crate::{
+    film::{FilterKind, PixelFilter},
+    output::ImageFormat,
+    post::{PostProcess, ToneMap},
+    sampler::SamplerKind,
+};
+
+// Settings for adaptive sampling, which stops taking samples for a pixel once
+// it's no longer noisy
+#[derive(Clone, Copy, Debug)]
+pub struct Adaptive {
+    // Largest standard error allowed, relative to the pixel's brightness
+    pub threshold: f32,
+    // Samples every pixel takes before it can be judged
+    pub min_samples: u64,
+}
+
+// Everything the renderer needs to know about a job, collected from the
+// command line
+pub struct RenderConfig {
+    pub width: u64,
+    pub height: u64,
+    pub scene: String,
+    // The most samples taken for each pixel, and the number taken when
+    // adaptive sampling is off
+    pub samples: u64,
+    pub adaptive: Option<Adaptive>,
+    // Image of the number of samples used for each pixel, for debugging
+    // adaptive sampling
+    pub sample_map: Option<(PathBuf, ImageFormat)>,
+    // Samples per pixel in each pass of a progressive render, the output is
+    // rewritten after every pass
+    pub pass_samples: Option<u64>,
+    // Where the numbers for pixel offsets, lens positions and bounces come from
+    pub sampler: SamplerKind,
+    // How samples are weighted towards the pixels around them
+    pub filter: PixelFilter,
+    pub max_depth: usize,
+    // Aim rays at lights as well as finding them by scattering, turned off
+    // to compare against the plain path tracer
+    pub sample_lights: bool,
+    // Trace a single wavelength along each path instead of RGB, so glass can
+    // split light into its colours
+    pub spectral: bool,
+    pub output: PathBuf,
+    pub format: ImageFormat,
+    // Seeds all the random numbers used to generate and render the scene,
+    // picked at random if it isn't given
+    pub seed: u64,
+    pub threads: usize,
+    pub export: Option<PathBuf>,
+    pub post: PostProcess,
+}
+
+pub fn get_config() -> RenderConfig {
     let matches = App::new("Simple Ray Tracer")
         .arg(
             Arg::with_name("dimensions")
@@ -13,21 +74,163 @@ pub fn get_config() -> (u64, u64, String) {
         )
         .arg(
             Arg::with_name("scene")
-                .help("The scene to render")
+                .help("The built-in scene to render, or the path to a scene file")
                 .short("s")
                 .long("scene")
                 .takes_value(true)
                 .value_name("scene"),
         )
+        .arg(
+            Arg::with_name("samples")
+                .help("The number of samples taken for each pixel")
+                .short("n")
+                .long("samples")
+                .takes_value(true)
+                .value_name("samples"),
+        )
+        .arg(
+            Arg::with_name("adaptive")
+                .help("Stop sampling pixels once their noise relative to their brightness is below this threshold, --samples becomes the most samples a pixel can take")
+                .long("adaptive")
+                .takes_value(true)
+                .value_name("threshold"),
+        )
+        .arg(
+            Arg::with_name("min-samples")
+                .help("The number of samples every pixel takes before adaptive sampling can stop it, defaults to 16")
+                .long("min-samples")
+                .takes_value(true)
+                .requires("adaptive")
+                .value_name("samples"),
+        )
+        .arg(
+            Arg::with_name("sample-map")
+                .help("Also write an image showing how many samples each pixel took, white is --samples")
+                .long("sample-map")
+                .takes_value(true)
+                .value_name("path"),
+        )
+        .arg(
+            Arg::with_name("progressive")
+                .help("Render in passes of this many samples per pixel, rewriting the output after each one")
+                .short("p")
+                .long("progressive")
+                .takes_value(true)
+                .value_name("samples"),
+        )
+        .arg(
+            Arg::with_name("sampler")
+                .help("How the points sampled in each pixel are spread out, defaults to independent")
+                .long("sampler")
+                .takes_value(true)
+                .possible_values(&SamplerKind::NAMES)
+                .value_name("sampler"),
+        )
+        .arg(
+            Arg::with_name("filter")
+                .help("The filter used to weight samples towards the pixels around them, defaults to box")
+                .long("filter")
+                .takes_value(true)
+                .possible_values(&FilterKind::NAMES)
+                .value_name("filter"),
+        )
+        .arg(
+            Arg::with_name("filter-radius")
+                .help("How far the filter reaches from each sample in pixels, defaults to 0.5 for box, 1.5 for gaussian, 2 for mitchell and 3 for lanczos")
+                .long("filter-radius")
+                .takes_value(true)
+                .value_name("pixels"),
+        )
+        .arg(
+            Arg::with_name("max-depth")
+                .help("The maximum number of times a ray can bounce")
+                .long("max-depth")
+                .takes_value(true)
+                .value_name("depth"),
+        )
+        .arg(
+            Arg::with_name("naive")
+                .help("Only find lights by scattering rays instead of also aiming rays at them, for comparison")
+                .long("naive"),
+        )
+        .arg(
+            Arg::with_name("spectral")
+                .help("Trace a single wavelength of light along each path so glass with dispersion splits light into colours, takes more samples to converge")
+                .long("spectral"),
+        )
+        .arg(
+            Arg::with_name("output")
+                .help("The file to write the image to, the format is chosen from its extension")
+                .short("o")
+                .long("output")
+                .takes_value(true)
+                .value_name("path"),
+        )
+        .arg(
+            Arg::with_name("format")
+                .help("The output image format, overrides the output file's extension")
+                .short("f")
+                .long("format")
+                .takes_value(true)
+                .possible_values(&ImageFormat::NAMES)
+                .value_name("format"),
+        )
+        .arg(
+            Arg::with_name("seed")
+                .help("Seed for the random numbers, the same seed and options always give the same image")
+                .long("seed")
+                .takes_value(true)
+                .value_name("seed"),
+        )
+        .arg(
+            Arg::with_name("threads")
+                .help("The number of threads to render with, defaults to one per core")
+                .short("t")
+                .long("threads")
+                .takes_value(true)
+                .value_name("threads"),
+        )
+        .arg(
+            Arg::with_name("export")
+                .help("Write the scene to a scene file instead of rendering it")
+                .short("e")
+                .long("export")
+                .takes_value(true)
+                .value_name("path"),
+        )
+        .arg(
+            Arg::with_name("exposure")
+                .help("Multiplier for the brightness of the image")
+                .long("exposure")
+                .takes_value(true)
+                .value_name("exposure"),
+        )
+        .arg(
+            Arg::with_name("clamp")
+                .help("The brightest value allowed in each colour channel before tone mapping")
+                .long("clamp")
+                .takes_value(true)
+                .value_name("max"),
+        )
+        .arg(
+            Arg::with_name("tone-map")
+                .help("The tone mapping operator used to fit bright values into the image")
+                .long("tone-map")
+                .takes_value(true)
+                .possible_values(&ToneMap::NAMES)
+                .value_name("operator"),
+        )
         .get_matches();
 
-    let (width, height): (u64, u64) = match matches.values_of("dimensions") {
-        Some(mut vals) => (
-            // These unwraps are a bit ugly but they should be ok, the outer
-            // match on `matches.values_of()` should catch any errors.
-            vals.next().unwrap().parse().unwrap(),
-            vals.next().unwrap().parse().unwrap(),
-        ),
+    let (width, height) = match matches.values_of("dimensions") {
+        Some(vals) => {
+            // `number_of_values` makes sure there are exactly two
+            let vals: Vec<&str> = vals.collect();
+            (
+                parse_positive("dimensions", vals[0]),
+                parse_positive("dimensions", vals[1]),
+            )
+        }
         None => (200, 100),
     };
 
@@ -36,5 +239,181 @@ pub fn get_config() -> (u64, u64, String) {
         None => "default".to_owned(),
     };
 
-    (width, height, scene)
+    let samples = positive_value_of(&matches, "samples").unwrap_or(100);
+    let adaptive = positive_float_of(&matches, "adaptive").map(|threshold| Adaptive {
+        threshold,
+        min_samples: positive_value_of(&matches, "min-samples").unwrap_or(16),
+    });
+    let pass_samples = positive_value_of(&matches, "progressive");
+    let sampler = matches
+        .value_of("sampler")
+        .map_or(SamplerKind::Independent, |val| {
+            SamplerKind::from_name(val).unwrap()
+        });
+    let mut filter = PixelFilter::new(
+        matches
+            .value_of("filter")
+            .map_or(FilterKind::Box, |val| FilterKind::from_name(val).unwrap()),
+    );
+    if let Some(radius) = positive_float_of(&matches, "filter-radius") {
+        filter.radius = radius;
+    }
+    let max_depth = positive_value_of(&matches, "max-depth").unwrap_or(50);
+    let seed = matches
+        .value_of("seed")
+        .map_or_else(rand::random, |val| parse("seed", val));
+
+    let threads = positive_value_of(&matches, "threads")
+        .unwrap_or_else(|| available_parallelism().map_or(1, |n| n.get()));
+
+    let format = matches
+        .value_of("format")
+        // `possible_values` has already rejected anything unknown
+        .map(|val| ImageFormat::from_name(val).unwrap());
+
+    let (output, format) = match (matches.value_of("output"), format) {
+        (Some(path), Some(format)) => (PathBuf::from(path), format),
+        (Some(path), None) => {
+            let path = PathBuf::from(path);
+            match ImageFormat::from_path(&path) {
+                Some(format) => (path, format),
+                None => unknown_format(&path),
+            }
+        }
+        (None, format) => {
+            let format = format.unwrap_or(ImageFormat::Ppm);
+            let path = PathBuf::from("output").join(format!("output.{}", format.extension()));
+            (path, format)
+        }
+    };
+
+    let sample_map = matches.value_of("sample-map").map(|path| {
+        let path = PathBuf::from(path);
+        match ImageFormat::from_path(&path) {
+            Some(format) => (path, format),
+            None => invalid_value(&format!(
+                "Can't tell the image format of sample map '{}', give it a .ppm, .png or .pfm extension",
+                path.display()
+            )),
+        }
+    });
+
+    let export = matches.value_of("export").map(PathBuf::from);
+
+    let post = PostProcess {
+        exposure: positive_float_of(&matches, "exposure").unwrap_or(1.0),
+        clamp: positive_float_of(&matches, "clamp"),
+        tone_map: matches
+            .value_of("tone-map")
+            .map_or(ToneMap::None, |val| ToneMap::from_name(val).unwrap()),
+    };
+
+    RenderConfig {
+        width,
+        height,
+        scene,
+        samples,
+        adaptive,
+        sample_map,
+        pass_samples,
+        sampler,
+        filter,
+        max_depth,
+        sample_lights: !matches.is_present("naive"),
+        spectral: matches.is_present("spectral"),
+        output,
+        format,
+        seed,
+        threads,
+        export,
+        post,
+    }
+}
+
+// Parse an option that has to be a number above zero
+fn positive_value_of<T>(matches: &ArgMatches, name: &str) -> Option<T>
+where
+    T: FromStr + PartialOrd + Default,
+{
+    matches.value_of(name).map(|val| parse_positive(name, val))
+}
+
+// The same for floating point options, which also have to be finite
+fn positive_float_of(matches: &ArgMatches, name: &str) -> Option<f32> {
+    matches
+        .value_of(name)
+        .map(|val| positive_float(val).unwrap_or_else(|msg| invalid_option(name, &msg)))
+}
+
+fn parse_positive<T>(name: &str, val: &str) -> T
+where
+    T: FromStr + PartialOrd + Default,
+{
+    positive(val).unwrap_or_else(|msg| invalid_option(name, &msg))
+}
+
+fn parse<T: FromStr>(name: &str, val: &str) -> T {
+    val.parse()
+        .unwrap_or_else(|_| invalid_option(name, &not_a_number(val)))
+}
+
+fn positive<T>(val: &str) -> Result<T, String>
+where
+    T: FromStr + PartialOrd + Default,
+{
+    let parsed: T = val.parse().map_err(|_| not_a_number(val))?;
+    // Compared this way round so NaN is turned away too
+    if parsed.partial_cmp(&T::default()) != Some(Ordering::Greater) {
+        return Err("must be above zero".to_owned());
+    }
+    Ok(parsed)
+}
+
+fn positive_float(val: &str) -> Result<f32, String> {
+    let parsed: f32 = positive(val)?;
+    if !parsed.is_finite() {
+        return Err("must be finite".to_owned());
+    }
+    Ok(parsed)
+}
+
+fn not_a_number(val: &str) -> String {
+    format!("'{}' isn't a valid number", val)
+}
+
+fn invalid_option(name: &str, msg: &str) -> ! {
+    invalid_value(&format!("Invalid value for '--{}': {}", name, msg))
+}
+
+fn unknown_format(path: &Path) -> ! {
+    invalid_value(&format!(
+        "Can't tell the image format of '{}', give it one of the extensions {} or use --format",
+        path.display(),
+        ImageFormat::NAMES.join(", ")
+    ))
+}
+
+fn invalid_value(msg: &str) -> ! {
+    Error::with_description(msg, ErrorKind::InvalidValue).exit()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{positive, positive_float};
+
+    #[test]
+    fn positive_numbers() {
+        assert_eq!(positive::<u64>("12"), Ok(12));
+        assert!(positive::<u64>("0").is_err());
+        assert!(positive::<u64>("-3").is_err());
+        assert!(positive::<u64>("ten").is_err());
+
+        assert_eq!(positive_float("0.5"), Ok(0.5));
+        assert!(positive_float("0").is_err());
+        assert!(positive_float("-1.5").is_err());
+        // These parse fine as floats but would turn the image into NaNs
+        for val in ["NaN", "inf", "-inf", "infinity"] {
+            assert!(positive_float(val).is_err(), "{}", val);
+        }
+    }
 }
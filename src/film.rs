@@ -0,0 +1,275 @@
+use std::f32::consts::PI;
+
+use crate::vector::Vec3;
+
+// Reconstruction filters deciding how much each sample counts towards the
+// pixels around it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FilterKind {
+    // Every sample counts equally towards the pixels it lands in
+    Box,
+    Gaussian,
+    // Mitchell-Netravali with B = C = 1/3
+    Mitchell,
+    // Windowed sinc with as many lobes as the radius
+    Lanczos,
+}
+
+impl FilterKind {
+    pub const NAMES: [&'static str; 4] = ["box", "gaussian", "mitchell", "lanczos"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "box" => Some(FilterKind::Box),
+            "gaussian" => Some(FilterKind::Gaussian),
+            "mitchell" => Some(FilterKind::Mitchell),
+            "lanczos" => Some(FilterKind::Lanczos),
+            _ => None,
+        }
+    }
+
+    // Radius in pixels used when none is given. A box this size keeps each
+    // sample inside its own pixel.
+    pub fn default_radius(self) -> f32 {
+        match self {
+            FilterKind::Box => 0.5,
+            FilterKind::Gaussian => 1.5,
+            FilterKind::Mitchell => 2.0,
+            FilterKind::Lanczos => 3.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct PixelFilter {
+    pub kind: FilterKind,
+    // How far from a sample, in pixels, the filter reaches
+    pub radius: f32,
+}
+
+impl PixelFilter {
+    pub fn new(kind: FilterKind) -> Self {
+        PixelFilter {
+            kind,
+            radius: kind.default_radius(),
+        }
+    }
+
+    // Weight of a sample `x` pixels away along one axis. The filters are
+    // separable so the weight in 2D is the product of both axes.
+    fn weight(&self, x: f32) -> f32 {
+        let x = x.abs();
+        if x > self.radius {
+            return 0.0;
+        }
+        match self.kind {
+            FilterKind::Box => 1.0,
+            FilterKind::Gaussian => {
+                // Three standard deviations fit inside the radius, and the
+                // curve is lowered so it reaches zero at the edge
+                let sigma = self.radius / 3.0;
+                let gaussian = |x: f32| (-x * x / (2.0 * sigma * sigma)).exp();
+                gaussian(x) - gaussian(self.radius)
+            }
+            FilterKind::Mitchell => {
+                let (b, c) = (1.0 / 3.0, 1.0 / 3.0);
+                // The curve is defined out to 2
+                let x = 2.0 * x / self.radius;
+                let val = if x < 1.0 {
+                    (12.0 - 9.0 * b - 6.0 * c) * x * x * x
+                        + (-18.0 + 12.0 * b + 6.0 * c) * x * x
+                        + (6.0 - 2.0 * b)
+                } else {
+                    (-b - 6.0 * c) * x * x * x
+                        + (6.0 * b + 30.0 * c) * x * x
+                        + (-12.0 * b - 48.0 * c) * x
+                        + (8.0 * b + 24.0 * c)
+                };
+                val / 6.0
+            }
+            FilterKind::Lanczos => sinc(x) * sinc(x / self.radius),
+        }
+    }
+
+    // The pixels along one axis a sample at `pos` reaches, along with their
+    // weights. Pixel `k` covers `k..k + 1` so its centre is at `k + 0.5`, and
+    // it takes samples from `radius` before its centre up to but not
+    // including `radius` after, so a sample on the edge of a box filter only
+    // goes to one pixel.
+    fn footprint(&self, pos: f32, first: u64, last: u64) -> Vec<(u64, f32)> {
+        let start = ((pos - 0.5 - self.radius).floor() + 1.0).max(first as f32) as u64;
+        let end = (pos - 0.5 + self.radius).floor().min(last as f32);
+        if end < start as f32 {
+            return Vec::new();
+        }
+        (start..=end as u64)
+            .map(|k| (k, self.weight(k as f32 + 0.5 - pos)))
+            .filter(|&(_, weight)| weight != 0.0)
+            .collect()
+    }
+}
+
+fn sinc(x: f32) -> f32 {
+    if x < 1e-5 {
+        return 1.0;
+    }
+    let x = PI * x;
+    x.sin() / x
+}
+
+// Weighted total of the samples splatted onto a pixel
+#[derive(Clone, Copy, Debug)]
+struct FilmPixel {
+    sum: Vec3,
+    weight: f32,
+}
+
+impl Default for FilmPixel {
+    fn default() -> Self {
+        FilmPixel {
+            sum: Vec3::new(0.0, 0.0, 0.0),
+            weight: 0.0,
+        }
+    }
+}
+
+// The image being built up from the filtered samples
+pub struct Film {
+    width: u64,
+    pixels: Vec<FilmPixel>,
+}
+
+impl Film {
+    pub fn new(width: u64, height: u64) -> Self {
+        Film {
+            width,
+            pixels: vec![FilmPixel::default(); (width * height) as usize],
+        }
+    }
+
+    // Add a tile's samples to the image. Floating point addition depends on
+    // the order it's done in, so tiles have to be merged in the same order
+    // every time for renders to repeat exactly.
+    pub fn merge(&mut self, tile: &FilmTile) {
+        let start = (tile.top * self.width) as usize;
+        let pixels = &mut self.pixels[start..start + tile.pixels.len()];
+        for (pixel, splatted) in pixels.iter_mut().zip(tile.pixels.iter()) {
+            pixel.sum += splatted.sum;
+            pixel.weight += splatted.weight;
+        }
+    }
+
+    // The filtered image, top row first
+    pub fn image(&self) -> Vec<Vec3> {
+        self.pixels
+            .iter()
+            .map(|pixel| {
+                if pixel.weight == 0.0 {
+                    Vec3::new(0.0, 0.0, 0.0)
+                } else {
+                    pixel.sum / pixel.weight
+                }
+            })
+            .collect()
+    }
+}
+
+// Samples from one row of pixels, kept apart from the film so rows can be
+// rendered on different threads
+pub struct FilmTile {
+    top: u64,
+    width: u64,
+    pixels: Vec<FilmPixel>,
+}
+
+impl FilmTile {
+    // Somewhere to splat the samples taken for row `row` of a `width` by
+    // `height` image, covering every row the filter can reach from it
+    pub fn new((width, height): (u64, u64), row: u64, filter: &PixelFilter) -> Self {
+        let reach = (filter.radius - 0.5).ceil().max(0.0) as u64;
+        let top = row.saturating_sub(reach);
+        let bottom = (row + reach).min(height - 1);
+        FilmTile {
+            top,
+            width,
+            pixels: vec![FilmPixel::default(); ((bottom - top + 1) * width) as usize],
+        }
+    }
+
+    // Splat a sample taken at `(x, y)` in pixel coordinates, measured from the
+    // top left of the image
+    pub fn add_sample(&mut self, filter: &PixelFilter, (x, y): (f32, f32), col: Vec3) {
+        let rows = self.pixels.len() as u64 / self.width;
+        let columns = filter.footprint(x, 0, self.width - 1);
+        for (l, weight_y) in filter.footprint(y, self.top, self.top + rows - 1) {
+            let start = ((l - self.top) * self.width) as usize;
+            for &(k, weight_x) in columns.iter() {
+                let weight = weight_x * weight_y;
+                let pixel = &mut self.pixels[start + k as usize];
+                pixel.sum += weight * col;
+                pixel.weight += weight;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        film::{Film, FilmTile, FilterKind, PixelFilter},
+        vector::Vec3,
+    };
+
+    #[test]
+    fn filter_shapes() {
+        for name in FilterKind::NAMES.iter() {
+            let filter = PixelFilter::new(FilterKind::from_name(name).unwrap());
+            assert!(filter.weight(0.0) > 0.0, "{}", name);
+            assert!(filter.weight(0.3) <= filter.weight(0.0), "{}", name);
+            assert_eq!(filter.weight(filter.radius + 1e-3), 0.0, "{}", name);
+            // All but the box fade out towards the edge
+            if filter.kind != FilterKind::Box {
+                assert!(filter.weight(filter.radius - 1e-3).abs() < 1e-2, "{}", name);
+            }
+        }
+
+        // Both of these sharpen with a negative lobe
+        let mitchell = PixelFilter::new(FilterKind::Mitchell);
+        assert!(mitchell.weight(1.5) < 0.0);
+        let lanczos = PixelFilter::new(FilterKind::Lanczos);
+        assert!(lanczos.weight(1.5) < 0.0);
+        assert!(lanczos.weight(1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn splatting() {
+        let mut film = Film::new(4, 3);
+
+        // A box filter leaves each sample in its own pixel
+        let filter = PixelFilter::new(FilterKind::Box);
+        let mut tile = FilmTile::new((4, 3), 1, &filter);
+        tile.add_sample(&filter, (2.5, 1.5), Vec3::new(1.0, 0.0, 0.0));
+        tile.add_sample(&filter, (2.0, 1.0), Vec3::new(0.0, 1.0, 0.0));
+        film.merge(&tile);
+        let image = film.image();
+        assert_eq!(image[6], Vec3::new(0.5, 0.5, 0.0));
+        assert_eq!(image.iter().filter(|&&col| col.x > 0.0).count(), 1);
+
+        // Wider filters reach the neighbouring rows and columns, which still
+        // come out the colour of the only sample that reached them
+        let mut film = Film::new(4, 3);
+        let filter = PixelFilter::new(FilterKind::Gaussian);
+        let mut tile = FilmTile::new((4, 3), 0, &filter);
+        tile.add_sample(&filter, (0.2, 0.7), Vec3::new(0.0, 0.0, 2.0));
+        film.merge(&tile);
+        let image = film.image();
+        for (i, col) in image.iter().enumerate() {
+            let (k, l) = (i % 4, i / 4);
+            if k < 2 && l < 2 {
+                assert!((col.z - 2.0).abs() < 1e-5);
+            } else {
+                assert_eq!(col.z, 0.0);
+            }
+        }
+    }
+}
@@ -1,101 +1,152 @@
 use indicatif::{ProgressBar, ProgressStyle};
 
 use std::{
-    f32::MAX,
-    fs::{create_dir, File},
-    io::Write,
-    path::Path,
+    fs::{create_dir_all, rename},
+    io,
+    path::{Path, PathBuf},
+    process::exit,
 };
 
+mod aabb;
+mod bvh;
+mod color;
+mod matrix;
 mod vector;
 use vector::Vec3;
-mod ray;
-use ray::Ray;
-mod shapes;
-use shapes::Hittable;
 mod camera;
 mod material;
+mod ray;
+mod shapes;
 mod utils;
-use utils::gen_random;
+use utils::seed_random;
+mod film;
+use film::Film;
+mod render;
+use render::{render, PixelStats};
 mod scene;
-use scene::load_scene;
+use scene::{export_scene, load_scene};
 mod config;
-use config::get_config;
-
-fn color(ray: Ray, world: &dyn Hittable, depth: usize) -> Vec3 {
-    match world.hit(&ray, 0.001, MAX) {
-        Some(hit) => {
-            if depth < 50 {
-                if let Some((att, scattered)) = hit.mat.scatter(ray, hit.clone()) {
-                    att * color(scattered, world, depth + 1)
-                } else {
-                    Vec3::new(0.0, 0.0, 0.0)
-                }
-            } else {
-                Vec3::new(0.0, 0.0, 0.0)
-            }
+use config::{get_config, RenderConfig};
+mod output;
+use output::{write_image, ImageFormat};
+mod post;
+mod sampler;
+mod spectrum;
+
+fn main() {
+    let config = get_config();
+    let (x, y) = (config.width, config.height);
+
+    // Scene generation happens on this thread so seeding it is enough to
+    // make the scene repeatable. Rendering reseeds for each pixel.
+    seed_random(config.seed);
+
+    let description = load_scene(&config.scene).unwrap_or_else(|err| {
+        eprintln!("Could not load scene: {}", err);
+        exit(1);
+    });
+
+    if let Some(path) = &config.export {
+        if let Err(err) = export_scene(&description, path) {
+            eprintln!("Could not export scene: {}", err);
+            exit(1);
         }
-        None => color_background(ray),
+        return;
     }
-}
-
-fn color_background(ray: Ray) -> Vec3 {
-    let unit_dir = ray.dir.get_unit();
-    // Interpolate along y axis
-    let t = (unit_dir.y + 1.0) * 0.5;
-    ((1.0 - t) * Vec3::new(1.0, 1.0, 1.0)) + (t * Vec3::new(0.5, 0.7, 1.0))
-}
 
-fn main() {
-    let (x, y, scene) = get_config();
-    let s: u64 = 100;
+    // Get Scene
+    let scene = description.build(x, y).unwrap_or_else(|err| {
+        eprintln!("Could not build scene: {}", err);
+        exit(1);
+    });
+    for warning in scene.warnings.iter() {
+        eprintln!("Warning: {}", warning);
+    }
 
-    if !Path::new("output").is_dir() {
-        create_dir("output").unwrap();
+    if let Some(dir) = config.output.parent() {
+        if !dir.as_os_str().is_empty() && !dir.is_dir() {
+            create_dir_all(dir).unwrap_or_else(|err| {
+                eprintln!("Could not create {}: {}", dir.display(), err);
+                exit(1);
+            });
+        }
     }
 
-    let mut output = File::create("output/output.ppm").unwrap();
-    let header = format!("P3\n{} {}\n255\n", x, y);
-    output.write_all(header.as_bytes()).unwrap();
+    // Without progressive rendering everything is done in one pass
+    let pass_samples = config.pass_samples.unwrap_or(config.samples);
+    let passes = config.samples.div_ceil(pass_samples);
 
     // Setup progress indicator
-    let progress = initialise_progress_indicator(y);
-
-    // Get Scene
-    let (cam, world) = load_scene(scene, x, y);
-
-    for i in 0..y {
-        for j in 0..x {
-            let mut col = Vec3::new(0.0, 0.0, 0.0);
-
-            for _ in 0..s {
-                let u = (j as f32 + gen_random()) / x as f32;
-                let v = 1.0 - ((i as f32 + gen_random()) / y as f32);
+    let progress = initialise_progress_indicator(y * passes);
+
+    // Totals of every sample taken so far for each pixel
+    let mut accumulated = vec![PixelStats::default(); (x * y) as usize];
+    let mut film = Film::new(x, y);
+    let mut done = 0;
+    for pass in 1..=passes {
+        if passes > 1 {
+            progress.set_message(&format!("Pass {}/{}", pass, passes));
+        }
 
-                let ray = cam.get_ray(u, v);
+        let samples = pass_samples.min(config.samples - done);
+        let mut next = Vec::with_capacity(accumulated.len());
+        render(
+            &scene,
+            &config,
+            samples,
+            &accumulated,
+            &mut film,
+            &progress,
+            |row| next.extend_from_slice(row),
+        );
+        accumulated = next;
+        done += samples;
+
+        let pixels: Vec<Vec3> = film
+            .image()
+            .into_iter()
+            .map(|col| config.post.apply(col))
+            .collect();
+        if let Err(err) = write_snapshot(&config.output, config.format, &config, &pixels) {
+            eprintln!("Could not write {}: {}", config.output.display(), err);
+            exit(1);
+        }
 
-                col += color(ray, &world, 0);
+        if let Some((path, format)) = &config.sample_map {
+            let pixels: Vec<Vec3> = accumulated
+                .iter()
+                .map(|stats| {
+                    let val = stats.count as f32 / config.samples as f32;
+                    Vec3::new(val, val, val)
+                })
+                .collect();
+            if let Err(err) = write_snapshot(path, *format, &config, &pixels) {
+                eprintln!("Could not write {}: {}", path.display(), err);
+                exit(1);
             }
-
-            col /= s as f32;
-
-            // Gamma correction
-            col.x = col.x.sqrt();
-            col.y = col.y.sqrt();
-            col.z = col.z.sqrt();
-
-            let r = (col.x * 255.0) as usize;
-            let g = (col.y * 255.0) as usize;
-            let b = (col.z * 255.0) as usize;
-
-            let pixel = format!("{} {} {}\n", r, g, b);
-            output.write_all(pixel.as_bytes()).unwrap();
         }
-        progress.inc(1);
     }
     progress.finish_with_message("Finished!");
 }
 
+// Write the image to a temporary file next to the output and then move it
+// into place, so stopping the render part way through a write never leaves
+// a broken image behind
+fn write_snapshot(
+    path: &Path,
+    format: ImageFormat,
+    config: &RenderConfig,
+    pixels: &[Vec3],
+) -> io::Result<()> {
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".partial");
+    let partial = PathBuf::from(partial);
+
+    let size = (config.width, config.height);
+    write_image(&partial, format, size, pixels)?;
+    rename(&partial, path)
+}
+
 fn initialise_progress_indicator(steps: u64) -> ProgressBar {
     let progress_style = ProgressStyle::default_bar()
         .template("{msg} {bar:80.green/white} {pos:>4}/{len} [{elapsed}]")
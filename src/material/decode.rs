@@ -0,0 +1,253 @@
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufReader, Read},
+    path::{Path, PathBuf},
+};
+
+// Decoded image as 8 bit RGB data, row by row from the top
+pub struct DecodedImage {
+    pub data: Vec<u8>,
+    pub width: usize,
+    pub height: usize,
+}
+
+#[derive(Debug)]
+pub enum ImageError {
+    Io(PathBuf, io::Error),
+    Unsupported(PathBuf),
+    Decode(PathBuf, String),
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ImageError::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+            ImageError::Unsupported(path) => write!(
+                f,
+                "{}: unsupported image format, expected PPM, PNG or JPEG",
+                path.display()
+            ),
+            ImageError::Decode(path, msg) => write!(f, "{}: {}", path.display(), msg),
+        }
+    }
+}
+
+// Decode a PPM, PNG or JPEG file. The format is worked out from the start of
+// the file rather than trusting the extension.
+pub fn decode_image(path: &Path) -> Result<DecodedImage, ImageError> {
+    let mut bytes = Vec::new();
+    File::open(path)
+        .and_then(|file| BufReader::new(file).read_to_end(&mut bytes))
+        .map_err(|err| ImageError::Io(path.to_owned(), err))?;
+
+    let decoded = match bytes.as_slice() {
+        [b'P', b'3', ..] | [b'P', b'6', ..] => decode_ppm(&bytes),
+        [0x89, b'P', b'N', b'G', ..] => decode_png(&bytes),
+        [0xFF, 0xD8, ..] => decode_jpeg(&bytes),
+        _ => return Err(ImageError::Unsupported(path.to_owned())),
+    };
+    decoded.map_err(|msg| ImageError::Decode(path.to_owned(), msg))
+}
+
+// Both the ASCII (P3) and binary (P6) forms
+fn decode_ppm(bytes: &[u8]) -> Result<DecodedImage, String> {
+    let binary = bytes[1] == b'6';
+    let mut pos = 2;
+
+    // The header is whitespace separated and can have `#` comments anywhere
+    let mut header = [0usize; 3];
+    for val in header.iter_mut() {
+        *val = ppm_number(bytes, &mut pos)?;
+    }
+    let [width, height, max_val] = header;
+    if width == 0 || height == 0 {
+        return Err("image has no pixels".to_owned());
+    }
+    if max_val == 0 || max_val > 65535 {
+        return Err(format!("invalid maximum value {}", max_val));
+    }
+
+    let len = width
+        .checked_mul(height)
+        .and_then(|pixels| pixels.checked_mul(3))
+        .ok_or_else(|| "image is too large".to_owned())?;
+    let samples: Vec<usize> = if binary {
+        // Exactly one whitespace character separates the header and data
+        pos += 1;
+        let size = if max_val < 256 { 1 } else { 2 };
+        let end = len
+            .checked_mul(size)
+            .and_then(|size| size.checked_add(pos))
+            .ok_or_else(|| "image is too large".to_owned())?;
+        let data = bytes
+            .get(pos..end)
+            .ok_or_else(|| "image data is too short".to_owned())?;
+        data.chunks(size)
+            .map(|sample| sample.iter().fold(0, |acc, &b| (acc << 8) | b as usize))
+            .collect()
+    } else {
+        (0..len)
+            .map(|_| ppm_number(bytes, &mut pos))
+            .collect::<Result<_, _>>()?
+    };
+
+    let data = samples
+        .into_iter()
+        .map(|sample| (sample.min(max_val) * 255 / max_val) as u8)
+        .collect();
+    Ok(DecodedImage {
+        data,
+        width,
+        height,
+    })
+}
+
+fn ppm_number(bytes: &[u8], pos: &mut usize) -> Result<usize, String> {
+    loop {
+        match bytes.get(*pos) {
+            Some(b'#') => {
+                while !matches!(bytes.get(*pos), Some(b'\n') | None) {
+                    *pos += 1;
+                }
+            }
+            Some(b) if b.is_ascii_whitespace() => *pos += 1,
+            _ => break,
+        }
+    }
+
+    let start = *pos;
+    while bytes.get(*pos).is_some_and(u8::is_ascii_digit) {
+        *pos += 1;
+    }
+    if start == *pos {
+        return Err("expected a number".to_owned());
+    }
+    std::str::from_utf8(&bytes[start..*pos])
+        .unwrap()
+        .parse()
+        .map_err(|err| format!("invalid number: {}", err))
+}
+
+fn decode_png(bytes: &[u8]) -> Result<DecodedImage, String> {
+    let mut decoder = png::Decoder::new(bytes);
+    // Expand palettes and low bit depths, and cut 16 bit channels down to 8
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info().map_err(|err| err.to_string())?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader
+        .next_frame(&mut buffer)
+        .map_err(|err| err.to_string())?;
+    buffer.truncate(info.buffer_size());
+
+    // Alpha is dropped, textures are always opaque
+    let data = match info.color_type {
+        png::ColorType::Rgb => buffer,
+        png::ColorType::Rgba => rgb_from(&buffer, 4, |p| [p[0], p[1], p[2]]),
+        png::ColorType::Grayscale => rgb_from(&buffer, 1, |p| [p[0]; 3]),
+        png::ColorType::GrayscaleAlpha => rgb_from(&buffer, 2, |p| [p[0]; 3]),
+        png::ColorType::Indexed => return Err("palette wasn't expanded".to_owned()),
+    };
+
+    Ok(DecodedImage {
+        data,
+        width: info.width as usize,
+        height: info.height as usize,
+    })
+}
+
+fn decode_jpeg(bytes: &[u8]) -> Result<DecodedImage, String> {
+    let mut decoder = jpeg_decoder::Decoder::new(bytes);
+    let pixels = decoder.decode().map_err(|err| err.to_string())?;
+    let info = decoder
+        .info()
+        .ok_or_else(|| "missing image info".to_owned())?;
+
+    let data = match info.pixel_format {
+        jpeg_decoder::PixelFormat::RGB24 => pixels,
+        jpeg_decoder::PixelFormat::L8 => rgb_from(&pixels, 1, |p| [p[0]; 3]),
+        jpeg_decoder::PixelFormat::CMYK32 => rgb_from(&pixels, 4, |p| {
+            let k = 255 - p[3] as u32;
+            let channel = |c: u8| ((255 - c as u32) * k / 255) as u8;
+            [channel(p[0]), channel(p[1]), channel(p[2])]
+        }),
+        jpeg_decoder::PixelFormat::L16 => return Err("16 bit JPEGs aren't supported".to_owned()),
+    };
+
+    Ok(DecodedImage {
+        data,
+        width: info.width as usize,
+        height: info.height as usize,
+    })
+}
+
+// Convert pixels of `channels` bytes each to RGB
+fn rgb_from<F>(buffer: &[u8], channels: usize, to_rgb: F) -> Vec<u8>
+where
+    F: Fn(&[u8]) -> [u8; 3],
+{
+    buffer.chunks(channels).flat_map(to_rgb).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{
+        material::decode::{decode_image, ImageError},
+        output::{write_image, ImageFormat},
+        vector::Vec3,
+    };
+
+    #[test]
+    fn decode_files() {
+        let dir = std::env::temp_dir().join(format!(
+            "simple-ray-tracer-decode-test-{}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+
+        let ascii = dir.join("ascii.ppm");
+        fs::write(&ascii, "P3\n# comment\n2 1\n15\n15 0 0  0 15 15\n").unwrap();
+        let image = decode_image(&ascii).unwrap();
+        assert_eq!((image.width, image.height), (2, 1));
+        assert_eq!(image.data, vec![255, 0, 0, 0, 255, 255]);
+
+        // Write with the output code and make sure the same pixels come back
+        let pixels = vec![
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+            Vec3::new(1.0, 1.0, 1.0),
+        ];
+        let expected = vec![255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255];
+        for (name, format) in [
+            ("binary.ppm", ImageFormat::Ppm),
+            ("image.png", ImageFormat::Png),
+        ] {
+            let path = dir.join(name);
+            write_image(&path, format, (2, 2), &pixels).unwrap();
+            let image = decode_image(&path).unwrap();
+            assert_eq!((image.width, image.height), (2, 2));
+            assert_eq!(image.data, expected);
+        }
+
+        let unsupported = dir.join("image.gif");
+        fs::write(&unsupported, "GIF89a").unwrap();
+        assert!(matches!(
+            decode_image(&unsupported),
+            Err(ImageError::Unsupported(_))
+        ));
+
+        // A header claiming more pixels than fit in memory is an error, not
+        // an overflow
+        let huge = dir.join("huge.ppm");
+        fs::write(&huge, format!("P6\n{} {}\n255\n", usize::MAX / 2, 3)).unwrap();
+        match decode_image(&huge) {
+            Err(ImageError::Decode(_, msg)) => assert_eq!(msg, "image is too large"),
+            _ => panic!("huge image wasn't rejected"),
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,14 +1,65 @@
-use std::{default::Default, sync::Arc};
+use serde::{Deserialize, Serialize};
+
+use std::{
+    default::Default,
+    f32::consts::{FRAC_1_PI, PI},
+    sync::Arc,
+};
 
 use crate::{
     material::{SolidColor, Texture},
     ray::{Ray, RayHit},
-    utils::{gen_random, random_in_unit_sphere},
-    vector::{dot, Vec3},
+    sampler::Sampler,
+    utils::{random_cosine_direction, random_in_unit_sphere, random_unit_vector},
+    vector::{dot, Onb, Vec3},
 };
 
-pub trait Material {
-    fn scatter(&self, ray: Ray, hit: RayHit) -> Option<(Vec3, Ray)>;
+// A direction picked by a material for the path to carry on in
+#[derive(Clone, Copy, Debug)]
+pub struct BsdfSample {
+    // Unit direction the light arrives from
+    pub dir: Vec3,
+    // The BSDF times the cosine of the angle `dir` makes with the surface,
+    // which is what light arriving along `dir` gets multiplied by
+    pub value: Vec3,
+    // Probability density of picking `dir`, per unit solid angle
+    pub pdf: f32,
+    // Whether `dir` was one of a few exact directions, like a mirror's
+    // reflection. `value` and `pdf` then only make sense as a ratio, and
+    // aiming rays at lights from here would never help.
+    pub specular: bool,
+}
+
+// How a surface reflects and transmits light. `ray` is the ray that found
+// `hit`, so light leaves back along its reverse.
+pub trait Material: Send + Sync {
+    // Pick a direction to continue a path in, or `None` if the path ends here
+    fn sample(&self, ray: &Ray, hit: &RayHit, sampler: &mut dyn Sampler) -> Option<BsdfSample>;
+
+    // The BSDF times the cosine for light arriving from `dir`, which is
+    // always zero for specular materials
+    fn eval(&self, _ray: &Ray, _hit: &RayHit, _dir: Vec3) -> Vec3 {
+        Vec3::new(0.0, 0.0, 0.0)
+    }
+
+    // Probability density of `sample` picking `dir`, again zero for
+    // specular materials
+    fn pdf(&self, _ray: &Ray, _hit: &RayHit, _dir: Vec3) -> f32 {
+        0.0
+    }
+
+    // Whether everything `sample` can pick at `hit` is specular, so aiming
+    // rays at lights from there would never help. Materials that mix smooth
+    // and rough layers aren't, whichever layer a given sample came from.
+    fn is_specular(&self, _ray: &Ray, _hit: &RayHit) -> bool {
+        false
+    }
+
+    // Light given off by the material at a point, most materials don't emit
+    // anything
+    fn emitted(&self, _u: f32, _v: f32, _p: Vec3) -> Vec3 {
+        Vec3::new(0.0, 0.0, 0.0)
+    }
 }
 
 #[derive(Clone)]
@@ -23,10 +74,29 @@ impl Lambertian {
 }
 
 impl Material for Lambertian {
-    fn scatter(&self, ray: Ray, hit: RayHit) -> Option<(Vec3, Ray)> {
-        let target = hit.point + hit.normal + random_in_unit_sphere();
-        let new_ray = Ray::new(hit.point, target - hit.point, ray.time);
-        Some((self.albedo.value(hit.u, hit.v, hit.point), new_ray))
+    // Cosine weighted directions, which follow the BSDF exactly so every
+    // sample carries the albedo
+    fn sample(&self, ray: &Ray, hit: &RayHit, sampler: &mut dyn Sampler) -> Option<BsdfSample> {
+        let basis = Onb::new(facing(hit.normal, ray.dir));
+        let dir = basis.local_to_world(random_cosine_direction(sampler));
+        Some(BsdfSample {
+            dir,
+            value: self.eval(ray, hit, dir),
+            pdf: self.pdf(ray, hit, dir),
+            specular: false,
+        })
+    }
+
+    fn eval(&self, ray: &Ray, hit: &RayHit, dir: Vec3) -> Vec3 {
+        let albedo = self
+            .albedo
+            .value_filtered(hit.u, hit.v, hit.point, hit.footprint);
+        albedo * self.pdf(ray, hit, dir)
+    }
+
+    fn pdf(&self, ray: &Ray, hit: &RayHit, dir: Vec3) -> f32 {
+        let cosine = dot(&facing(hit.normal, ray.dir), &dir.get_unit());
+        cosine.max(0.0) * FRAC_1_PI
     }
 }
 
@@ -53,53 +123,253 @@ impl Metal {
 }
 
 impl Material for Metal {
-    fn scatter(&self, ray: Ray, hit: RayHit) -> Option<(Vec3, Ray)> {
+    fn sample(&self, ray: &Ray, hit: &RayHit, sampler: &mut dyn Sampler) -> Option<BsdfSample> {
         let reflected = reflected(ray.dir.get_unit(), hit.normal);
-        let fuzz = self.fuzz * random_in_unit_sphere();
-        let new_ray = Ray::new(hit.point, reflected + fuzz, ray.time);
-        if dot(&new_ray.dir, &hit.normal) > 0.0 {
-            return Some((self.albedo.value(hit.u, hit.v, hit.point), new_ray));
+        let dir = reflected + self.fuzz * random_in_unit_sphere(sampler);
+        if dot(&dir, &hit.normal) <= 0.0 {
+            return None;
         }
-        None
+        let albedo = self
+            .albedo
+            .value_filtered(hit.u, hit.v, hit.point, hit.footprint);
+        Some(specular(dir, albedo))
+    }
+
+    fn is_specular(&self, _ray: &Ray, _hit: &RayHit) -> bool {
+        true
     }
 }
 
+// Glass, water and the like. Perfectly clear unless given an absorption,
+// and a surface colour can tint the light that goes through. With a
+// dispersion curve rays carrying a wavelength get their own refractive
+// index, otherwise `refractive_index` is used for everything.
 pub struct Dielectric {
     pub refractive_index: f32,
+    pub absorption: Option<Absorption>,
+    pub surface_color: Option<Arc<dyn Texture>>,
+    pub dispersion: Option<Dispersion>,
 }
 
 impl Dielectric {
     pub fn new(refractive_index: f32) -> Self {
-        Dielectric { refractive_index }
+        Dielectric {
+            refractive_index,
+            absorption: None,
+            surface_color: None,
+            dispersion: None,
+        }
+    }
+
+    pub fn with_absorption(mut self, absorption: Absorption) -> Self {
+        self.absorption = Some(absorption);
+        self
+    }
+
+    pub fn with_surface_color(mut self, surface_color: Arc<dyn Texture>) -> Self {
+        self.surface_color = Some(surface_color);
+        self
+    }
+
+    pub fn with_dispersion(mut self, dispersion: Dispersion) -> Self {
+        self.dispersion = Some(dispersion);
+        self
+    }
+
+    fn refractive_index_for(&self, ray: &Ray) -> f32 {
+        match (&self.dispersion, ray.wavelength) {
+            (Some(dispersion), Some(wavelength)) => dispersion.refractive_index(wavelength),
+            _ => self.refractive_index,
+        }
     }
 }
 
 impl Material for Dielectric {
-    fn scatter(&self, ray: Ray, hit: RayHit) -> Option<(Vec3, Ray)> {
-        let attenuation = Vec3::new(1.0, 1.0, 1.0);
+    fn sample(&self, ray: &Ray, hit: &RayHit, sampler: &mut dyn Sampler) -> Option<BsdfSample> {
+        let mut attenuation = Vec3::new(1.0, 1.0, 1.0);
+        let refractive_index = self.refractive_index_for(ray);
 
-        let (outward_normal, rfx, cosine) = if dot(&ray.dir, &hit.normal) > 0.0 {
-            let cosine = self.refractive_index * dot(&ray.dir, &hit.normal) / ray.dir.get_mag();
-            (-hit.normal, self.refractive_index, cosine)
+        let inside = dot(&ray.dir, &hit.normal) > 0.0;
+        let (outward_normal, rfx, cosine) = if inside {
+            let cosine = refractive_index * dot(&ray.dir, &hit.normal) / ray.dir.get_mag();
+            (-hit.normal, refractive_index, cosine)
         } else {
             let cosine = -dot(&ray.dir, &hit.normal) / ray.dir.get_mag();
-            (hit.normal, 1.0 / self.refractive_index, cosine)
+            (hit.normal, 1.0 / refractive_index, cosine)
         };
 
+        // A ray hitting the inside has come from the last place it crossed
+        // the surface, assuming nothing else is inside, so whatever it brings
+        // back has been absorbed along the way
+        if let Some(absorption) = self.absorption.filter(|_| inside) {
+            attenuation = absorption.transmittance(hit.t * ray.dir.get_mag());
+        }
+
         if let Some(refracted) = refracted(ray.dir, outward_normal, rfx) {
-            let reflect_prob = schlick(cosine, self.refractive_index);
-            if gen_random() >= reflect_prob {
-                let refracted_ray = Ray::new(hit.point, refracted, ray.time);
-                return Some((attenuation, refracted_ray));
+            let reflect_prob = schlick(cosine, refractive_index);
+            if sampler.get_1d() >= reflect_prob {
+                if let Some(surface_color) = &self.surface_color {
+                    attenuation = attenuation
+                        * surface_color.value_filtered(hit.u, hit.v, hit.point, hit.footprint);
+                }
+                return Some(specular(refracted, attenuation));
             }
         }
 
-        let reflected_ray = Ray::new(hit.point, reflected(ray.dir, hit.normal), ray.time);
-        Some((attenuation, reflected_ray))
+        Some(specular(reflected(ray.dir, hit.normal), attenuation))
+    }
+
+    fn is_specular(&self, _ray: &Ray, _hit: &RayHit) -> bool {
+        true
+    }
+}
+
+// Light absorbed inside a material following the Beer-Lambert law. `color` is
+// what white light turns into after going one unit through it at a `density`
+// of 1, and higher densities absorb more quickly.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Absorption {
+    pub color: Vec3,
+    pub density: f32,
+}
+
+impl Absorption {
+    // Fraction of each channel left after going `distance` through the
+    // material
+    pub fn transmittance(&self, distance: f32) -> Vec3 {
+        let channel = |color: f32| {
+            // A channel that's completely absorbed would give infinities
+            let coefficient = -color.clamp(1e-6, 1.0).ln() * self.density;
+            (-coefficient * distance).exp()
+        };
+        Vec3::new(
+            channel(self.color.x),
+            channel(self.color.y),
+            channel(self.color.z),
+        )
     }
 }
 
-fn reflected(input: Vec3, normal: Vec3) -> Vec3 {
+// How a material's refractive index changes with the wavelength of light,
+// which is what splits white light into a rainbow. Wavelengths in the
+// formulas are in micrometres.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Dispersion {
+    // `a + b / λ²`, a simple fit that works well for most glasses
+    Cauchy { a: f32, b: f32 },
+    // `n² = 1 + Σ b λ² / (λ² - c)`, the form glass makers publish their
+    // measurements in
+    Sellmeier { b: [f32; 3], c: [f32; 3] },
+}
+
+impl Dispersion {
+    // Crown glass, the usual glass for lenses and windows
+    pub const BK7: Dispersion = Dispersion::Sellmeier {
+        b: [1.039_612, 0.231_792_34, 1.010_469_5],
+        c: [0.006_000_699, 0.020_017_914, 103.560_65],
+    };
+
+    // Dense flint glass, which spreads colours out much more
+    pub const SF11: Dispersion = Dispersion::Sellmeier {
+        b: [1.737_597, 0.313_747_34, 1.898_781],
+        c: [0.013_188_707, 0.062_306_814, 155.236_3],
+    };
+
+    // Refractive index for light of `wavelength` nanometres
+    pub fn refractive_index(&self, wavelength: f32) -> f32 {
+        let l2 = (wavelength / 1000.0).powi(2);
+        match self {
+            Dispersion::Cauchy { a, b } => a + b / l2,
+            Dispersion::Sellmeier { b, c } => {
+                let sum: f32 = b.iter().zip(c.iter()).map(|(b, c)| b * l2 / (l2 - c)).sum();
+                (1.0 + sum).sqrt()
+            }
+        }
+    }
+}
+
+// A light source, it only emits light and doesn't scatter any
+#[derive(Clone)]
+pub struct DiffuseLight {
+    pub emit: Arc<dyn Texture>,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Arc<dyn Texture>) -> Self {
+        DiffuseLight { emit }
+    }
+}
+
+impl Material for DiffuseLight {
+    fn sample(&self, _ray: &Ray, _hit: &RayHit, _sampler: &mut dyn Sampler) -> Option<BsdfSample> {
+        None
+    }
+
+    fn emitted(&self, u: f32, v: f32, p: Vec3) -> Vec3 {
+        self.emit.value(u, v, p)
+    }
+}
+
+// Phase function for participating media, light is scattered equally in all
+// directions
+#[derive(Clone)]
+pub struct Isotropic {
+    pub albedo: Arc<dyn Texture>,
+}
+
+impl Isotropic {
+    pub fn new(albedo: Arc<dyn Texture>) -> Self {
+        Isotropic { albedo }
+    }
+}
+
+impl Material for Isotropic {
+    fn sample(&self, ray: &Ray, hit: &RayHit, sampler: &mut dyn Sampler) -> Option<BsdfSample> {
+        let dir = random_unit_vector(sampler);
+        Some(BsdfSample {
+            dir,
+            value: self.eval(ray, hit, dir),
+            pdf: self.pdf(ray, hit, dir),
+            specular: false,
+        })
+    }
+
+    // There's no surface so no cosine either
+    fn eval(&self, ray: &Ray, hit: &RayHit, dir: Vec3) -> Vec3 {
+        let albedo = self
+            .albedo
+            .value_filtered(hit.u, hit.v, hit.point, hit.footprint);
+        albedo * self.pdf(ray, hit, dir)
+    }
+
+    fn pdf(&self, _ray: &Ray, _hit: &RayHit, _dir: Vec3) -> f32 {
+        1.0 / (4.0 * PI)
+    }
+}
+
+// Sample for a material that only scatters in the direction `dir`, letting
+// through `attenuation` of the light
+pub fn specular(dir: Vec3, attenuation: Vec3) -> BsdfSample {
+    BsdfSample {
+        dir: dir.get_unit(),
+        value: attenuation,
+        pdf: 1.0,
+        specular: true,
+    }
+}
+
+// The normal flipped if need be to face back towards where `dir` came from,
+// so surfaces look the same from both sides
+pub fn facing(normal: Vec3, dir: Vec3) -> Vec3 {
+    if dot(&normal, &dir) > 0.0 {
+        -normal
+    } else {
+        normal
+    }
+}
+
+pub fn reflected(input: Vec3, normal: Vec3) -> Vec3 {
     input - (2.0 * dot(&input, &normal) * normal)
 }
 
@@ -119,3 +389,138 @@ fn schlick(cosine: f32, refractive_index: f32) -> f32 {
     let r = ((1.0 - refractive_index) / (1.0 + refractive_index)).powi(2);
     r + (1.0 - r) * (1.0 - cosine).powi(5)
 }
+
+// Check that what `mat` samples for `ray` at `hit` agrees with its `eval`
+// and `pdf`, that the average weight of the samples is within `albedo`, and
+// that the density over every direction adds up to about one. Shared by the
+// tests of the materials that work out all three themselves.
+#[cfg(test)]
+pub(super) fn check_bsdf(
+    mat: &dyn Material,
+    ray: &Ray,
+    hit: &RayHit,
+    albedo: std::ops::Range<f32>,
+) {
+    let mut sampler = crate::sampler::SamplerKind::Independent.build(0, 1);
+
+    let n = 20000;
+    let mut total = 0.0;
+    for _ in 0..n {
+        let bsdf = match mat.sample(ray, hit, sampler.as_mut()) {
+            Some(bsdf) => bsdf,
+            None => continue,
+        };
+        let pdf = mat.pdf(ray, hit, bsdf.dir);
+        assert!((bsdf.pdf - pdf).abs() <= 1e-3 * pdf.max(1.0));
+        let value = mat.eval(ray, hit, bsdf.dir);
+        assert!((bsdf.value - value).get_mag() <= 1e-3 * value.get_mag().max(1.0));
+        total += bsdf.value.x / bsdf.pdf;
+    }
+    let average = total / n as f32;
+    assert!(albedo.contains(&average), "{}", average);
+
+    // No more than one, and not much less even for materials that send
+    // light both ways
+    let mut total = 0.0;
+    for _ in 0..n {
+        let dir = random_unit_vector(sampler.as_mut());
+        total += mat.pdf(ray, hit, dir);
+    }
+    let integral = total * 4.0 * PI / n as f32;
+    assert!(integral > 0.8 && integral < 1.05, "{}", integral);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        material::{Absorption, Dielectric, Dispersion, Lambertian, Material, SolidColor},
+        ray::{Ray, RayHit},
+        sampler::SamplerKind,
+        utils::seed_random,
+        vector::{dot, Vec3},
+    };
+
+    #[test]
+    fn lambertian_sampling() {
+        seed_random(5);
+        let mut sampler = SamplerKind::Independent.build(0, 1);
+        let mat = Arc::new(Lambertian::new(Arc::new(SolidColor::new(0.2, 0.4, 0.6))));
+        let normal = Vec3::new(0.0, 1.0, 0.0);
+        let hit = RayHit::new(1.0, 0.0, 0.0, Vec3::new(0.0, 0.0, 0.0), normal, mat.clone());
+
+        // Seen from below the surface should scatter downwards instead
+        for ray_dir in [Vec3::new(1.0, -1.0, 0.0), Vec3::new(1.0, 1.0, 0.0)] {
+            let ray = Ray::new(-ray_dir, ray_dir, 0.0);
+            let side = -ray_dir.y.signum();
+
+            let n = 20000;
+            let mut total_cos = 0.0;
+            for _ in 0..n {
+                let bsdf = mat.sample(&ray, &hit, sampler.as_mut()).unwrap();
+                let cosine = side * dot(&bsdf.dir, &normal);
+                assert!(cosine >= 0.0);
+                assert!((bsdf.pdf - mat.pdf(&ray, &hit, bsdf.dir)).abs() < 1e-5);
+                let weight = bsdf.value / bsdf.pdf;
+                assert!((weight - Vec3::new(0.2, 0.4, 0.6)).get_mag() < 1e-5);
+                total_cos += cosine;
+            }
+            // Cosine weighted directions average two thirds
+            assert!((total_cos / n as f32 - 2.0 / 3.0).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn absorbing_glass() {
+        seed_random(3);
+        let mut sampler = SamplerKind::Independent.build(0, 1);
+        let absorption = Absorption {
+            color: Vec3::new(0.25, 0.5, 1.0),
+            density: 2.0,
+        };
+        let blue = Vec3::new(0.2, 0.4, 0.8);
+        let mat = Arc::new(
+            Dielectric::new(1.5)
+                .with_absorption(absorption)
+                .with_surface_color(Arc::new(SolidColor::new(blue.x, blue.y, blue.z))),
+        );
+        let normal = Vec3::new(0.0, 0.0, 1.0);
+        let hit = RayHit::new(0.5, 0.0, 0.0, Vec3::new(0.0, 0.0, 0.0), normal, mat.clone());
+        let white = Vec3::new(1.0, 1.0, 1.0);
+
+        // Going in head on nothing is absorbed yet, and only light passing
+        // through the surface is tinted
+        let ray = Ray::new(normal, -normal, 0.0);
+        for _ in 0..100 {
+            let bsdf = mat.sample(&ray, &hit, sampler.as_mut()).unwrap();
+            let expected = if bsdf.dir.z < 0.0 { blue } else { white };
+            assert!((bsdf.value - expected).get_mag() < 1e-5);
+        }
+
+        // Coming out after half a unit at density 2 leaves the absorption
+        // colour
+        let ray = Ray::new(-normal, normal, 0.0);
+        for _ in 0..100 {
+            let bsdf = mat.sample(&ray, &hit, sampler.as_mut()).unwrap();
+            let tint = if bsdf.dir.z > 0.0 { blue } else { white };
+            assert!((bsdf.value - absorption.color * tint).get_mag() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn dispersion_curves() {
+        // Published indices at the yellow helium line
+        assert!((Dispersion::BK7.refractive_index(587.6) - 1.5168).abs() < 1e-4);
+        assert!((Dispersion::SF11.refractive_index(587.6) - 1.7847).abs() < 1e-4);
+        let cauchy = Dispersion::Cauchy { a: 1.5, b: 0.004 };
+        assert!((cauchy.refractive_index(400.0) - 1.525).abs() < 1e-5);
+
+        // Blue light is always bent more than red
+        for dispersion in [Dispersion::BK7, Dispersion::SF11, cauchy].iter() {
+            let blue = dispersion.refractive_index(450.0);
+            let red = dispersion.refractive_index(650.0);
+            assert!(blue > red);
+        }
+    }
+}
@@ -0,0 +1,477 @@
+use serde::{Deserialize, Serialize};
+
+use std::{f32::consts::PI, sync::Arc};
+
+use crate::{
+    material::{
+        material::{facing, specular},
+        BsdfSample, Material, Texture,
+    },
+    ray::{Ray, RayHit},
+    sampler::Sampler,
+    vector::{cross, dot, Onb, Vec3},
+};
+
+// Materials whose surfaces are made of tiny perfect mirrors facing in
+// slightly different directions, spread out following the GGX (also known as
+// Trowbridge-Reitz) distribution. Everything is worked out in a frame where
+// the normal is the z axis and `wo`, the direction light leaves in, is above
+// the surface.
+
+// Roughness below this is treated as perfectly smooth, the distribution gets
+// too sharp to sample well anyway
+const SMOOTH: f32 = 1e-3;
+
+#[derive(Clone, Copy, Debug)]
+pub(super) struct Ggx {
+    alpha: f32,
+}
+
+impl Ggx {
+    // Roughness is squared to give `alpha`, which makes it look like it
+    // changes evenly between 0 and 1
+    pub(super) fn new(roughness: f32) -> Self {
+        let roughness = roughness.clamp(0.0, 1.0);
+        Ggx {
+            alpha: roughness * roughness,
+        }
+    }
+
+    pub(super) fn is_smooth(&self) -> bool {
+        self.alpha < SMOOTH
+    }
+
+    // Density of microfacets facing `h`
+    pub(super) fn d(&self, h: Vec3) -> f32 {
+        if h.z <= 0.0 {
+            return 0.0;
+        }
+        // Working the tangent out from the sideways parts keeps it accurate
+        // close to the normal, where sharp distributions need it most
+        let cos2 = h.z * h.z;
+        let tan2 = (h.x * h.x + h.y * h.y) / cos2;
+        let a2 = self.alpha * self.alpha;
+        a2 / (PI * cos2 * cos2 * (a2 + tan2).powi(2))
+    }
+
+    // Smith's auxiliary function, the area of microfacets hidden from `w` for
+    // each unit of visible area
+    fn lambda(&self, w: Vec3) -> f32 {
+        let cos2 = w.z * w.z;
+        if cos2 == 0.0 {
+            return f32::INFINITY;
+        }
+        let tan2 = (w.x * w.x + w.y * w.y) / cos2;
+        ((1.0 + self.alpha * self.alpha * tan2).sqrt() - 1.0) / 2.0
+    }
+
+    // Fraction of microfacets visible from `w`
+    fn g1(&self, w: Vec3) -> f32 {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    // Fraction visible from both `wo` and `wi`, allowing for higher
+    // microfacets being more likely to be visible from both
+    pub(super) fn g(&self, wo: Vec3, wi: Vec3) -> f32 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    // Pick a microfacet normal in proportion to how much of it can be seen
+    // from `wo`, using Heitz's method of sampling the visible normals
+    pub(super) fn sample_visible(&self, wo: Vec3, (u1, u2): (f32, f32)) -> Vec3 {
+        // Stretch the view so the distribution becomes a hemisphere
+        let vh = Vec3::new(self.alpha * wo.x, self.alpha * wo.y, wo.z).get_unit();
+        let len_sq = vh.x * vh.x + vh.y * vh.y;
+        let t1 = if len_sq > 0.0 {
+            Vec3::new(-vh.y, vh.x, 0.0) / len_sq.sqrt()
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let t2 = cross(&vh, &t1);
+
+        // Point on the half of the disk that's visible from `vh`
+        let r = u1.sqrt();
+        let phi = 2.0 * PI * u2;
+        let p1 = r * phi.cos();
+        let s = 0.5 * (1.0 + vh.z);
+        let p2 = (1.0 - s) * (1.0 - p1 * p1).sqrt() + s * r * phi.sin();
+
+        // Project it up onto the hemisphere and unstretch
+        let lift = (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt();
+        let nh = p1 * t1 + p2 * t2 + lift * vh;
+        Vec3::new(self.alpha * nh.x, self.alpha * nh.y, nh.z.max(0.0)).get_unit()
+    }
+
+    // Density of `sample_visible` picking `h`
+    pub(super) fn pdf_visible(&self, wo: Vec3, h: Vec3) -> f32 {
+        self.g1(wo) * dot(&wo, &h).max(0.0) * self.d(h) / wo.z
+    }
+}
+
+// Mirror `w` about `h`
+pub(super) fn reflect(w: Vec3, h: Vec3) -> Vec3 {
+    2.0 * dot(&w, &h) * h - w
+}
+
+// Bend `w` through a surface facing `h` into a material with `eta` times the
+// refractive index, `None` if it's totally internally reflected
+pub(super) fn refract(w: Vec3, h: Vec3, eta: f32) -> Option<Vec3> {
+    let cos_i = dot(&w, &h);
+    let sin2_t = (1.0 - cos_i * cos_i).max(0.0) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return None;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    Some(-w / eta + (cos_i / eta - cos_t) * h)
+}
+
+// Fraction of unpolarised light reflected by a dielectric surface, where
+// `eta` is the refractive index of the far side over that of the side the
+// light arrives from
+pub(super) fn fresnel_dielectric(cos_i: f32, eta: f32) -> f32 {
+    let cos_i = cos_i.clamp(0.0, 1.0);
+    let sin2_t = (1.0 - cos_i * cos_i) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    let parallel = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let perpendicular = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    (parallel * parallel + perpendicular * perpendicular) / 2.0
+}
+
+// The same for a conductor, which has a complex refractive index `eta + ik`
+// that differs for each colour channel
+fn fresnel_conductor(cos_i: f32, eta: Vec3, k: Vec3) -> Vec3 {
+    let channel = |eta: f32, k: f32| {
+        let cos2 = cos_i.clamp(0.0, 1.0).powi(2);
+        let sin2 = 1.0 - cos2;
+        let (eta2, k2) = (eta * eta, k * k);
+        let t0 = eta2 - k2 - sin2;
+        let a2_plus_b2 = (t0 * t0 + 4.0 * eta2 * k2).sqrt();
+        let a = (0.5 * (a2_plus_b2 + t0)).max(0.0).sqrt();
+        let t1 = a2_plus_b2 + cos2;
+        let t2 = 2.0 * cos_i * a;
+        let rs = (t1 - t2) / (t1 + t2);
+        let t3 = cos2 * a2_plus_b2 + sin2 * sin2;
+        let t4 = t2 * sin2;
+        let rp = rs * (t3 - t4) / (t3 + t4);
+        (rs + rp) / 2.0
+    };
+    Vec3::new(
+        channel(eta.x, k.x),
+        channel(eta.y, k.y),
+        channel(eta.z, k.z),
+    )
+}
+
+// Measured refractive indices of some common metals, averaged over the red,
+// green and blue parts of the spectrum
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConductorPreset {
+    Gold,
+    Copper,
+    Aluminium,
+}
+
+impl ConductorPreset {
+    // The real and imaginary parts of the refractive index
+    pub fn ior(self) -> (Vec3, Vec3) {
+        match self {
+            ConductorPreset::Gold => (
+                Vec3::new(0.143, 0.374, 1.442),
+                Vec3::new(3.983, 2.385, 1.603),
+            ),
+            ConductorPreset::Copper => (
+                Vec3::new(0.200, 0.924, 1.102),
+                Vec3::new(3.912, 2.452, 2.142),
+            ),
+            ConductorPreset::Aluminium => (
+                Vec3::new(1.657, 0.880, 0.521),
+                Vec3::new(9.224, 6.270, 4.837),
+            ),
+        }
+    }
+}
+
+// Metal, reflecting with the Fresnel equations for its complex refractive
+// index. `roughness` is read from the first channel of the texture.
+pub struct Conductor {
+    eta: Vec3,
+    k: Vec3,
+    roughness: Arc<dyn Texture>,
+}
+
+impl Conductor {
+    pub fn new(eta: Vec3, k: Vec3, roughness: Arc<dyn Texture>) -> Self {
+        Conductor { eta, k, roughness }
+    }
+
+    // The frame the surface is worked out in, `wo` in it and the
+    // distribution at the hit
+    fn local(&self, ray: &Ray, hit: &RayHit) -> (Onb, Vec3, Ggx) {
+        let basis = Onb::new(facing(hit.normal, ray.dir));
+        let wo = basis.world_to_local(-ray.dir.get_unit());
+        (basis, wo, roughness_at(&self.roughness, hit))
+    }
+
+    fn eval_local(&self, ggx: &Ggx, wo: Vec3, wi: Vec3) -> Vec3 {
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return Vec3::new(0.0, 0.0, 0.0);
+        }
+        let h = (wo + wi).get_unit();
+        let fresnel = fresnel_conductor(dot(&wo, &h), self.eta, self.k);
+        fresnel * (ggx.d(h) * ggx.g(wo, wi) / (4.0 * wo.z))
+    }
+
+    fn pdf_local(&self, ggx: &Ggx, wo: Vec3, wi: Vec3) -> f32 {
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return 0.0;
+        }
+        let h = (wo + wi).get_unit();
+        ggx.pdf_visible(wo, h) / (4.0 * dot(&wo, &h))
+    }
+}
+
+impl Material for Conductor {
+    fn sample(&self, ray: &Ray, hit: &RayHit, sampler: &mut dyn Sampler) -> Option<BsdfSample> {
+        let (basis, wo, ggx) = self.local(ray, hit);
+        if wo.z <= 0.0 {
+            return None;
+        }
+        if ggx.is_smooth() {
+            let wi = Vec3::new(-wo.x, -wo.y, wo.z);
+            let fresnel = fresnel_conductor(wo.z, self.eta, self.k);
+            return Some(specular(basis.local_to_world(wi), fresnel));
+        }
+
+        let h = ggx.sample_visible(wo, sampler.get_2d());
+        let wi = reflect(wo, h);
+        if wi.z <= 0.0 {
+            return None;
+        }
+        Some(BsdfSample {
+            dir: basis.local_to_world(wi),
+            value: self.eval_local(&ggx, wo, wi),
+            pdf: self.pdf_local(&ggx, wo, wi),
+            specular: false,
+        })
+    }
+
+    fn eval(&self, ray: &Ray, hit: &RayHit, dir: Vec3) -> Vec3 {
+        let (basis, wo, ggx) = self.local(ray, hit);
+        if ggx.is_smooth() {
+            return Vec3::new(0.0, 0.0, 0.0);
+        }
+        self.eval_local(&ggx, wo, basis.world_to_local(dir.get_unit()))
+    }
+
+    fn pdf(&self, ray: &Ray, hit: &RayHit, dir: Vec3) -> f32 {
+        let (basis, wo, ggx) = self.local(ray, hit);
+        if ggx.is_smooth() {
+            return 0.0;
+        }
+        self.pdf_local(&ggx, wo, basis.world_to_local(dir.get_unit()))
+    }
+
+    fn is_specular(&self, _ray: &Ray, hit: &RayHit) -> bool {
+        roughness_at(&self.roughness, hit).is_smooth()
+    }
+}
+
+// Glass with a rough surface, which both reflects and refracts. Like
+// `Dielectric` the change in radiance as light crosses the surface is left
+// out, it cancels out again once the light leaves.
+pub struct RoughDielectric {
+    refractive_index: f32,
+    roughness: Arc<dyn Texture>,
+}
+
+impl RoughDielectric {
+    pub fn new(refractive_index: f32, roughness: Arc<dyn Texture>) -> Self {
+        RoughDielectric {
+            refractive_index,
+            roughness,
+        }
+    }
+
+    // As for `Conductor`, along with the relative refractive index of the
+    // side below the surface
+    fn local(&self, ray: &Ray, hit: &RayHit) -> (Onb, Vec3, Ggx, f32) {
+        let (normal, eta) = if dot(&ray.dir, &hit.normal) < 0.0 {
+            (hit.normal, self.refractive_index)
+        } else {
+            (-hit.normal, 1.0 / self.refractive_index)
+        };
+        let basis = Onb::new(normal);
+        let wo = basis.world_to_local(-ray.dir.get_unit());
+        (basis, wo, roughness_at(&self.roughness, hit), eta)
+    }
+}
+
+impl Material for RoughDielectric {
+    fn sample(&self, ray: &Ray, hit: &RayHit, sampler: &mut dyn Sampler) -> Option<BsdfSample> {
+        let (basis, wo, ggx, eta) = self.local(ray, hit);
+        if wo.z <= 0.0 {
+            return None;
+        }
+
+        // Reflect or refract in proportion to the Fresnel term, about the
+        // normal itself if the surface is smooth
+        let h = if ggx.is_smooth() {
+            Vec3::new(0.0, 0.0, 1.0)
+        } else {
+            ggx.sample_visible(wo, sampler.get_2d())
+        };
+        let fresnel = fresnel_dielectric(dot(&wo, &h), eta);
+        let wi = if sampler.get_1d() < fresnel {
+            reflect(wo, h)
+        } else {
+            refract(wo, h, eta)?
+        };
+
+        let white = Vec3::new(1.0, 1.0, 1.0);
+        if ggx.is_smooth() {
+            return Some(specular(basis.local_to_world(wi), white));
+        }
+        let pdf = rough_dielectric_pdf(&ggx, wo, wi, eta);
+        if pdf == 0.0 {
+            return None;
+        }
+        Some(BsdfSample {
+            dir: basis.local_to_world(wi),
+            value: white * rough_dielectric_eval(&ggx, wo, wi, eta),
+            pdf,
+            specular: false,
+        })
+    }
+
+    fn eval(&self, ray: &Ray, hit: &RayHit, dir: Vec3) -> Vec3 {
+        let (basis, wo, ggx, eta) = self.local(ray, hit);
+        if ggx.is_smooth() {
+            return Vec3::new(0.0, 0.0, 0.0);
+        }
+        let val = rough_dielectric_eval(&ggx, wo, basis.world_to_local(dir.get_unit()), eta);
+        Vec3::new(val, val, val)
+    }
+
+    fn pdf(&self, ray: &Ray, hit: &RayHit, dir: Vec3) -> f32 {
+        let (basis, wo, ggx, eta) = self.local(ray, hit);
+        if ggx.is_smooth() {
+            return 0.0;
+        }
+        rough_dielectric_pdf(&ggx, wo, basis.world_to_local(dir.get_unit()), eta)
+    }
+
+    fn is_specular(&self, _ray: &Ray, hit: &RayHit) -> bool {
+        roughness_at(&self.roughness, hit).is_smooth()
+    }
+}
+
+// The microfacet normal that takes `wo` to `wi`, if it faces the right way
+// to do so
+fn half_vector(wo: Vec3, wi: Vec3, eta: f32) -> Option<Vec3> {
+    let h = if wi.z > 0.0 { wo + wi } else { wo + wi * eta };
+    let h = h.get_unit();
+    let h = if h.z < 0.0 { -h } else { h };
+    // Both directions have to be on the same sides of the microfacet as they
+    // are of the surface
+    if dot(&wo, &h) <= 0.0 || dot(&wi, &h) * wi.z <= 0.0 {
+        return None;
+    }
+    Some(h)
+}
+
+// The BSDF times the cosine of rough glass with relative refractive index
+// `eta`, which is the same for every colour
+pub(super) fn rough_dielectric_eval(ggx: &Ggx, wo: Vec3, wi: Vec3, eta: f32) -> f32 {
+    if wo.z <= 0.0 || wi.z == 0.0 {
+        return 0.0;
+    }
+    let h = match half_vector(wo, wi, eta) {
+        Some(h) => h,
+        None => return 0.0,
+    };
+    let fresnel = fresnel_dielectric(dot(&wo, &h), eta);
+    if wi.z > 0.0 {
+        fresnel * ggx.d(h) * ggx.g(wo, wi) / (4.0 * wo.z)
+    } else {
+        let (cos_oh, cos_ih) = (dot(&wo, &h), dot(&wi, &h));
+        let denom = (cos_ih + cos_oh / eta).powi(2) * wo.z;
+        (1.0 - fresnel) * ggx.d(h) * ggx.g(wo, wi) * (cos_ih * cos_oh).abs() / denom
+    }
+}
+
+// Density of picking `wi` by sampling a visible microfacet normal, then
+// reflecting or refracting in proportion to the Fresnel term
+pub(super) fn rough_dielectric_pdf(ggx: &Ggx, wo: Vec3, wi: Vec3, eta: f32) -> f32 {
+    if wo.z <= 0.0 || wi.z == 0.0 {
+        return 0.0;
+    }
+    let h = match half_vector(wo, wi, eta) {
+        Some(h) => h,
+        None => return 0.0,
+    };
+    let cos_oh = dot(&wo, &h);
+    let fresnel = fresnel_dielectric(cos_oh, eta);
+    if wi.z > 0.0 {
+        fresnel * ggx.pdf_visible(wo, h) / (4.0 * cos_oh)
+    } else {
+        let cos_ih = dot(&wi, &h);
+        // How much the refracted direction moves as the normal does
+        let jacobian = cos_ih.abs() / (cos_ih + cos_oh / eta).powi(2);
+        (1.0 - fresnel) * ggx.pdf_visible(wo, h) * jacobian
+    }
+}
+
+pub(super) fn roughness_at(roughness: &Arc<dyn Texture>, hit: &RayHit) -> Ggx {
+    let val = roughness.value_filtered(hit.u, hit.v, hit.point, hit.footprint);
+    Ggx::new(val.x)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        material::{
+            material::check_bsdf, microfacet::Ggx, Conductor, ConductorPreset, Material,
+            RoughDielectric, SolidColor,
+        },
+        ray::{Ray, RayHit},
+        utils::seed_random,
+        vector::Vec3,
+    };
+
+    #[test]
+    fn microfacet_sampling() {
+        seed_random(9);
+        let roughness = Arc::new(SolidColor::new(0.5, 0.5, 0.5));
+        let (eta, k) = ConductorPreset::Aluminium.ior();
+        let conductor: Arc<dyn Material> = Arc::new(Conductor::new(eta, k, roughness.clone()));
+        let glass: Arc<dyn Material> = Arc::new(RoughDielectric::new(1.5, roughness));
+
+        let normal = Vec3::new(0.0, 0.0, 1.0);
+        let outside = Vec3::new(0.6, 0.0, -0.8);
+        let inside = Vec3::new(0.3, 0.0, 0.954);
+        for (mat, ray_dir) in [(&conductor, outside), (&glass, outside), (&glass, inside)] {
+            let hit = RayHit::new(1.0, 0.0, 0.0, Vec3::new(0.0, 0.0, 0.0), normal, mat.clone());
+            let ray = Ray::new(-ray_dir, ray_dir, 0.0);
+            // Metal and glass shouldn't lose much at this roughness
+            check_bsdf(mat.as_ref(), &ray, &hit, 0.8..1.01);
+        }
+    }
+
+    #[test]
+    fn sharp_distribution() {
+        // Just off the normal of a very smooth surface, where `1 - cos²`
+        // would lose most of its digits
+        let ggx = Ggx::new(0.1);
+        let h = Vec3::new(0.003, 0.001, 1.0).get_unit();
+        let (a2, tan2) = (1e-4_f64, (0.003_f64.powi(2) + 0.001_f64.powi(2)));
+        let cos2 = 1.0 / (1.0 + tan2);
+        let expected = a2 / (std::f64::consts::PI * cos2 * cos2 * (a2 + tan2).powi(2));
+        assert!((ggx.d(h) as f64 / expected - 1.0).abs() < 1e-4);
+    }
+}
@@ -1,7 +1,17 @@
+pub(crate) mod decode;
+#[allow(clippy::module_inception)]
 mod material;
+mod microfacet;
 mod perlin;
+mod principled;
 mod texture;
 
-pub use material::{Dielectric, Lambertian, Material, Metal};
+pub use decode::ImageError;
+pub use material::{
+    Absorption, BsdfSample, Dielectric, DiffuseLight, Dispersion, Isotropic, Lambertian, Material,
+    Metal,
+};
+pub use microfacet::{Conductor, ConductorPreset, RoughDielectric};
 pub use perlin::Perlin;
-pub use texture::{Checkered, Image, Noise, SolidColor, Texture};
+pub use principled::Principled;
+pub use texture::{Checkered, Filter, Image, Noise, SolidColor, Texture, Wrap};
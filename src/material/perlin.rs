@@ -35,10 +35,10 @@ impl Perlin {
 
         let mut arr = [[[Vec3::new(0.0, 0.0, 0.0); 2]; 2]; 2];
 
-        for ii in 0..2 {
-            for jj in 0..2 {
-                for kk in 0..2 {
-                    arr[ii][jj][kk] = self.ran_vec[self.perm_x[(i + ii) & 255]
+        for (ii, plane) in arr.iter_mut().enumerate() {
+            for (jj, row) in plane.iter_mut().enumerate() {
+                for (kk, corner) in row.iter_mut().enumerate() {
+                    *corner = self.ran_vec[self.perm_x[(i + ii) & 255]
                         ^ self.perm_y[(j + jj) & 255]
                         ^ self.perm_z[(k + kk) & 255]];
                 }
@@ -79,7 +79,7 @@ fn generate() -> Vec<Vec3> {
     v
 }
 
-fn permute(p: &mut Vec<usize>, n: usize) {
+fn permute(p: &mut [usize], n: usize) {
     for i in (0..n).rev() {
         let target = gen_range(0.0, (i + 1) as f32);
         p.swap(i, target as usize);
@@ -102,16 +102,16 @@ fn interpolate(arr: &[[[Vec3; 2]; 2]; 2], u: f32, v: f32, w: f32) -> f32 {
 
     let mut accum = 0.0;
 
-    for i in 0..2 {
-        for j in 0..2 {
-            for k in 0..2 {
+    for (i, plane) in arr.iter().enumerate() {
+        for (j, row) in plane.iter().enumerate() {
+            for (k, corner) in row.iter().enumerate() {
                 let (ii, jj, kk) = (i as f32, j as f32, k as f32);
 
                 let weight = Vec3::new(u - ii, v - jj, w - kk);
                 let a = (ii * uu) + (1.0 - ii) * (1.0 - uu);
                 let b = (jj * vv) + (1.0 - jj) * (1.0 - vv);
                 let c = (kk * ww) + (1.0 - kk) * (1.0 - ww);
-                let d = dot(&arr[i][j][k], &weight);
+                let d = dot(corner, &weight);
 
                 accum += a * b * c * d;
             }
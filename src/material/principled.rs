@@ -0,0 +1,403 @@
+use std::{f32::consts::FRAC_1_PI, sync::Arc};
+
+use crate::{
+    color::luminance,
+    material::{
+        material::{facing, specular},
+        microfacet::{
+            fresnel_dielectric, reflect, refract, rough_dielectric_eval, rough_dielectric_pdf,
+            roughness_at, Ggx,
+        },
+        BsdfSample, Material, SolidColor, Texture,
+    },
+    ray::{Ray, RayHit},
+    sampler::Sampler,
+    utils::random_cosine_direction,
+    vector::{dot, Onb, Vec3},
+};
+
+// Roughness of the clear coat, which is always fairly glossy
+const CLEARCOAT_ROUGHNESS: f32 = 0.1;
+
+// Fraction of light the clear coat reflects head on, that of a varnish with
+// a refractive index of 1.5
+const CLEARCOAT_F0: f32 = 0.04;
+
+// One material covering most things, loosely following Disney's principled
+// BSDF. A base layer mixes diffuse, metal and glass, and a clear coat can be
+// laid over the top. Every parameter but the base colour is read from the
+// first channel of its texture and goes from 0 to 1.
+//
+// - `metallic` blends from a dielectric to a metal tinted by the base colour
+// - `roughness` applies to both the specular reflection and transmission
+// - `specular` sets how strongly dielectrics reflect, 0.5 is a refractive
+//   index of 1.5 which suits most things
+// - `clearcoat` is the strength of the coat
+// - `sheen` adds a soft white rim for cloth
+// - `transmission` blends from an opaque dielectric to glass tinted by the
+//   base colour
+pub struct Principled {
+    base_color: Arc<dyn Texture>,
+    metallic: Arc<dyn Texture>,
+    roughness: Arc<dyn Texture>,
+    specular: Arc<dyn Texture>,
+    clearcoat: Arc<dyn Texture>,
+    sheen: Arc<dyn Texture>,
+    transmission: Arc<dyn Texture>,
+}
+
+impl Principled {
+    // A rough plastic in `base_color`, the other parameters can then be set
+    // with the `with_*` methods
+    pub fn new(base_color: Arc<dyn Texture>) -> Self {
+        let solid = |val: f32| Arc::new(SolidColor::new(val, val, val)) as Arc<dyn Texture>;
+        Principled {
+            base_color,
+            metallic: solid(0.0),
+            roughness: solid(0.5),
+            specular: solid(0.5),
+            clearcoat: solid(0.0),
+            sheen: solid(0.0),
+            transmission: solid(0.0),
+        }
+    }
+
+    pub fn with_metallic(mut self, metallic: Arc<dyn Texture>) -> Self {
+        self.metallic = metallic;
+        self
+    }
+
+    pub fn with_roughness(mut self, roughness: Arc<dyn Texture>) -> Self {
+        self.roughness = roughness;
+        self
+    }
+
+    pub fn with_specular(mut self, specular: Arc<dyn Texture>) -> Self {
+        self.specular = specular;
+        self
+    }
+
+    pub fn with_clearcoat(mut self, clearcoat: Arc<dyn Texture>) -> Self {
+        self.clearcoat = clearcoat;
+        self
+    }
+
+    pub fn with_sheen(mut self, sheen: Arc<dyn Texture>) -> Self {
+        self.sheen = sheen;
+        self
+    }
+
+    pub fn with_transmission(mut self, transmission: Arc<dyn Texture>) -> Self {
+        self.transmission = transmission;
+        self
+    }
+
+    // The frame the surface is worked out in, `wo` in it and the parameters
+    // at the hit
+    fn local(&self, ray: &Ray, hit: &RayHit) -> (Onb, Vec3, Surface) {
+        let basis = Onb::new(facing(hit.normal, ray.dir));
+        let wo = basis.world_to_local(-ray.dir.get_unit());
+        let scalar = |texture: &Arc<dyn Texture>| {
+            let val = texture.value_filtered(hit.u, hit.v, hit.point, hit.footprint);
+            val.x.clamp(0.0, 1.0)
+        };
+        let base_color = self
+            .base_color
+            .value_filtered(hit.u, hit.v, hit.point, hit.footprint);
+
+        // Dielectrics reflecting `0.08 * specular` head on have this
+        // refractive index
+        let f0 = (0.08 * scalar(&self.specular)).sqrt().min(0.99);
+        let eta = (1.0 + f0) / (1.0 - f0);
+        // Light only crosses the surface from inside if it's transmissive,
+        // everything else looks the same from both sides
+        let inside = dot(&ray.dir, &hit.normal) > 0.0;
+
+        let surface = Surface::new(
+            wo,
+            base_color,
+            scalar(&self.metallic),
+            roughness_at(&self.roughness, hit),
+            eta,
+            if inside { 1.0 / eta } else { eta },
+            scalar(&self.clearcoat),
+            scalar(&self.sheen),
+            scalar(&self.transmission),
+        );
+        (basis, wo, surface)
+    }
+}
+
+impl Material for Principled {
+    // Pick one of the layers in proportion to roughly how much light it
+    // reflects, then weigh the direction by all of them so the layers can
+    // take over from each other smoothly
+    fn sample(&self, ray: &Ray, hit: &RayHit, sampler: &mut dyn Sampler) -> Option<BsdfSample> {
+        let (basis, wo, surface) = self.local(ray, hit);
+        if wo.z <= 0.0 {
+            return None;
+        }
+
+        let choice = sampler.get_1d();
+        let u = sampler.get_2d();
+        let [diffuse, glossy, transmit, coat] = surface.chances;
+        let wi = if choice < diffuse {
+            random_cosine_direction(sampler)
+        } else if choice < diffuse + glossy {
+            if surface.ggx.is_smooth() {
+                let wi = Vec3::new(-wo.x, -wo.y, wo.z);
+                let weight = surface.glossy * surface.under_coat / glossy;
+                let value = surface.glossy_fresnel(wo.z) * weight;
+                return Some(specular(basis.local_to_world(wi), value));
+            }
+            reflect(wo, surface.ggx.sample_visible(wo, u))
+        } else if choice < diffuse + glossy + transmit {
+            let h = if surface.ggx.is_smooth() {
+                Vec3::new(0.0, 0.0, 1.0)
+            } else {
+                surface.ggx.sample_visible(wo, u)
+            };
+            let fresnel = fresnel_dielectric(dot(&wo, &h), surface.eta_transmit);
+            let reflected = sampler.get_1d() < fresnel;
+            let wi = if reflected {
+                reflect(wo, h)
+            } else {
+                refract(wo, h, surface.eta_transmit)?
+            };
+            if surface.ggx.is_smooth() {
+                let weight = surface.transmission * surface.under_coat / transmit;
+                let tint = if reflected {
+                    Vec3::new(1.0, 1.0, 1.0)
+                } else {
+                    surface.base_color
+                };
+                return Some(specular(basis.local_to_world(wi), tint * weight));
+            }
+            wi
+        } else if coat > 0.0 {
+            reflect(wo, surface.coat_ggx.sample_visible(wo, u))
+        } else {
+            return None;
+        };
+
+        let pdf = surface.pdf(wo, wi);
+        if pdf == 0.0 {
+            return None;
+        }
+        Some(BsdfSample {
+            dir: basis.local_to_world(wi),
+            value: surface.eval(wo, wi),
+            pdf,
+            specular: false,
+        })
+    }
+
+    fn eval(&self, ray: &Ray, hit: &RayHit, dir: Vec3) -> Vec3 {
+        let (basis, wo, surface) = self.local(ray, hit);
+        surface.eval(wo, basis.world_to_local(dir.get_unit()))
+    }
+
+    fn pdf(&self, ray: &Ray, hit: &RayHit, dir: Vec3) -> f32 {
+        let (basis, wo, surface) = self.local(ray, hit);
+        surface.pdf(wo, basis.world_to_local(dir.get_unit()))
+    }
+
+    // Only smooth glossy reflection and transmission are specular, the
+    // diffuse layer and the coat never are
+    fn is_specular(&self, ray: &Ray, hit: &RayHit) -> bool {
+        let (_, _, surface) = self.local(ray, hit);
+        let [diffuse, _, _, coat] = surface.chances;
+        surface.ggx.is_smooth() && diffuse == 0.0 && coat == 0.0
+    }
+}
+
+// The material's parameters at one point, seen from `wo`
+struct Surface {
+    base_color: Vec3,
+    metallic: f32,
+    sheen: f32,
+    clearcoat: f32,
+    ggx: Ggx,
+    coat_ggx: Ggx,
+    // Relative refractive index the surface reflects with, and the one light
+    // crossing it sees
+    eta: f32,
+    eta_transmit: f32,
+    // How much of the base layer is diffuse, glossy reflection and
+    // transmission
+    diffuse: f32,
+    glossy: f32,
+    transmission: f32,
+    // Fraction of the light getting through the clear coat to the base layer
+    under_coat: f32,
+    // Chance of sampling the diffuse, glossy, transmission and coat lobes
+    chances: [f32; 4],
+}
+
+impl Surface {
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        wo: Vec3,
+        base_color: Vec3,
+        metallic: f32,
+        ggx: Ggx,
+        eta: f32,
+        eta_transmit: f32,
+        clearcoat: f32,
+        sheen: f32,
+        transmission: f32,
+    ) -> Self {
+        let dielectric = 1.0 - metallic;
+        let mut surface = Surface {
+            base_color,
+            metallic,
+            sheen,
+            clearcoat,
+            ggx,
+            coat_ggx: Ggx::new(CLEARCOAT_ROUGHNESS),
+            eta,
+            eta_transmit,
+            diffuse: dielectric * (1.0 - transmission),
+            glossy: 1.0 - dielectric * transmission,
+            transmission: dielectric * transmission,
+            under_coat: 1.0 - clearcoat * schlick(CLEARCOAT_F0, wo.z),
+            chances: [0.0; 4],
+        };
+
+        let chances = [
+            surface.diffuse * (luminance(base_color) + sheen),
+            surface.glossy * luminance(surface.glossy_fresnel(wo.z)),
+            surface.transmission,
+        ];
+        let coat = clearcoat * schlick(CLEARCOAT_F0, wo.z);
+        let total = surface.under_coat * chances.iter().sum::<f32>() + coat;
+        if total > 0.0 {
+            let [diffuse, glossy, transmit] = chances;
+            let base = surface.under_coat / total;
+            surface.chances = [diffuse * base, glossy * base, transmit * base, coat / total];
+        }
+        surface
+    }
+
+    // Fraction of light reflected off the base layer by a microfacet at
+    // `cos` to the light, tinted for metals
+    fn glossy_fresnel(&self, cos: f32) -> Vec3 {
+        let dielectric = fresnel_dielectric(cos, self.eta);
+        let white = Vec3::new(1.0, 1.0, 1.0);
+        let metal = self.base_color + (1.0 - cos).max(0.0).powi(5) * (white - self.base_color);
+        (1.0 - self.metallic) * Vec3::new(dielectric, dielectric, dielectric)
+            + self.metallic * metal
+    }
+
+    // The BSDF times the cosine, leaving out any perfectly smooth lobes
+    fn eval(&self, wo: Vec3, wi: Vec3) -> Vec3 {
+        let mut base = Vec3::new(0.0, 0.0, 0.0);
+        let mut coat = 0.0;
+        if wo.z <= 0.0 || wi.z == 0.0 {
+            return base;
+        }
+
+        if wi.z > 0.0 {
+            let h = (wo + wi).get_unit();
+            let cos_h = dot(&wo, &h);
+            // Light that isn't reflected by the surface goes into the diffuse
+            // layer
+            let entering = 1.0 - fresnel_dielectric(wo.z, self.eta);
+            let sheen = self.sheen * (1.0 - cos_h).max(0.0).powi(5);
+            let diffuse = self.base_color * FRAC_1_PI + Vec3::new(sheen, sheen, sheen);
+            base += diffuse * (self.diffuse * entering * wi.z);
+
+            if !self.ggx.is_smooth() {
+                let d = self.ggx.d(h) * self.ggx.g(wo, wi) / (4.0 * wo.z);
+                base += self.glossy_fresnel(cos_h) * (self.glossy * d);
+            }
+
+            let d = self.coat_ggx.d(h) * self.coat_ggx.g(wo, wi) / (4.0 * wo.z);
+            coat = self.clearcoat * schlick(CLEARCOAT_F0, cos_h) * d;
+        }
+
+        if !self.ggx.is_smooth() && self.transmission > 0.0 {
+            let glass = rough_dielectric_eval(&self.ggx, wo, wi, self.eta_transmit);
+            let tint = if wi.z > 0.0 {
+                Vec3::new(1.0, 1.0, 1.0)
+            } else {
+                self.base_color
+            };
+            base += tint * (self.transmission * glass);
+        }
+        base * self.under_coat + Vec3::new(coat, coat, coat)
+    }
+
+    fn pdf(&self, wo: Vec3, wi: Vec3) -> f32 {
+        if wo.z <= 0.0 || wi.z == 0.0 {
+            return 0.0;
+        }
+        let [diffuse, glossy, transmit, coat] = self.chances;
+        let mut pdf = 0.0;
+        if wi.z > 0.0 {
+            let h = (wo + wi).get_unit();
+            let cos_h = dot(&wo, &h);
+            pdf += diffuse * wi.z * FRAC_1_PI;
+            if !self.ggx.is_smooth() {
+                pdf += glossy * self.ggx.pdf_visible(wo, h) / (4.0 * cos_h);
+            }
+            pdf += coat * self.coat_ggx.pdf_visible(wo, h) / (4.0 * cos_h);
+        }
+        if !self.ggx.is_smooth() {
+            pdf += transmit * rough_dielectric_pdf(&self.ggx, wo, wi, self.eta_transmit);
+        }
+        pdf
+    }
+}
+
+// Schlick's approximation to the Fresnel term
+fn schlick(f0: f32, cos: f32) -> f32 {
+    f0 + (1.0 - f0) * (1.0 - cos).max(0.0).powi(5)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        material::{material::check_bsdf, Material, Principled, SolidColor, Texture},
+        ray::{Ray, RayHit},
+        utils::seed_random,
+        vector::Vec3,
+    };
+
+    fn solid(val: f32) -> Arc<dyn Texture> {
+        Arc::new(SolidColor::new(val, val, val))
+    }
+
+    #[test]
+    fn principled_sampling() {
+        seed_random(11);
+        let base = || Arc::new(SolidColor::new(0.9, 0.8, 0.7));
+        let materials: Vec<Arc<dyn Material>> = vec![
+            Arc::new(Principled::new(base())),
+            Arc::new(Principled::new(base()).with_metallic(solid(1.0))),
+            Arc::new(
+                Principled::new(base())
+                    .with_roughness(solid(0.8))
+                    .with_clearcoat(solid(1.0))
+                    .with_sheen(solid(1.0)),
+            ),
+            Arc::new(
+                Principled::new(base())
+                    .with_metallic(solid(0.3))
+                    .with_transmission(solid(1.0))
+                    .with_clearcoat(solid(0.5)),
+            ),
+        ];
+
+        let normal = Vec3::new(0.0, 0.0, 1.0);
+        for mat in materials.iter() {
+            let hit = RayHit::new(1.0, 0.0, 0.0, Vec3::new(0.0, 0.0, 0.0), normal, mat.clone());
+            let ray_dir = Vec3::new(0.6, 0.0, -0.8);
+            let ray = Ray::new(-ray_dir, ray_dir, 0.0);
+            // The coloured base soaks up some of the light
+            check_bsdf(mat.as_ref(), &ray, &hit, 0.5..1.01);
+        }
+    }
+}
@@ -1,9 +1,24 @@
-use std::sync::Arc;
+use serde::{Deserialize, Serialize};
 
-use crate::{material::Perlin, vector::Vec3};
+use std::{path::Path, sync::Arc};
 
-pub trait Texture {
+use crate::{
+    color::ColorSpace,
+    material::{
+        decode::{decode_image, ImageError},
+        Perlin,
+    },
+    vector::Vec3,
+};
+
+pub trait Texture: Send + Sync {
     fn value(&self, u: f32, v: f32, p: Vec3) -> Vec3;
+
+    // Look up the texture averaged over an area `footprint` wide in texture
+    // coordinates, only textures that can be filtered need to override this
+    fn value_filtered(&self, u: f32, v: f32, p: Vec3, _footprint: f32) -> Vec3 {
+        self.value(u, v, p)
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -39,10 +54,14 @@ impl Checkered {
 
 impl Texture for Checkered {
     fn value(&self, u: f32, v: f32, p: Vec3) -> Vec3 {
+        self.value_filtered(u, v, p, 0.0)
+    }
+
+    fn value_filtered(&self, u: f32, v: f32, p: Vec3, footprint: f32) -> Vec3 {
         let sines = (10.0 * p.x).sin() * (10.0 * p.y).sin() * (10.0 * p.z).sin();
         match sines {
-            val if val < 0.0 => self.odd.value(u, v, p),
-            _ => self.even.value(u, v, p),
+            val if val < 0.0 => self.odd.value_filtered(u, v, p, footprint),
+            _ => self.even.value_filtered(u, v, p, footprint),
         }
     }
 }
@@ -72,38 +91,262 @@ impl Texture for Noise {
     }
 }
 
+// How texels are blended together when looking up an image
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Filter {
+    #[default]
+    Nearest,
+    Bilinear,
+    // Bilinear lookups in the two closest mip levels for the ray's footprint,
+    // blended together
+    Trilinear,
+}
+
+// What happens to texture coordinates outside of 0 to 1
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Wrap {
+    #[default]
+    Clamp,
+    Repeat,
+    Mirror,
+}
+
+impl Wrap {
+    fn index(self, i: i64, n: usize) -> usize {
+        let n = n as i64;
+        let wrapped = match self {
+            Wrap::Clamp => i.clamp(0, n - 1),
+            Wrap::Repeat => i.rem_euclid(n),
+            Wrap::Mirror => {
+                let i = i.rem_euclid(2 * n);
+                if i < n {
+                    i
+                } else {
+                    2 * n - 1 - i
+                }
+            }
+        };
+        wrapped as usize
+    }
+}
+
 #[derive(Clone)]
-pub struct Image {
-    data: Vec<u8>,
+struct MipLevel {
+    texels: Vec<Vec3>,
     nx: usize,
     ny: usize,
 }
 
+impl MipLevel {
+    fn texel(&self, i: i64, j: i64, wrap: Wrap) -> Vec3 {
+        self.texels[wrap.index(i, self.nx) + self.nx * wrap.index(j, self.ny)]
+    }
+
+    fn nearest(&self, u: f32, v: f32, wrap: Wrap) -> Vec3 {
+        let i = (u * self.nx as f32).floor() as i64;
+        let j = ((1.0 - v) * self.ny as f32).floor() as i64;
+        self.texel(i, j, wrap)
+    }
+
+    fn bilinear(&self, u: f32, v: f32, wrap: Wrap) -> Vec3 {
+        // Texel centres are half a texel in from their edges
+        let x = u * self.nx as f32 - 0.5;
+        let y = (1.0 - v) * self.ny as f32 - 0.5;
+        let (i, j) = (x.floor(), y.floor());
+        let (fx, fy) = (x - i, y - j);
+        let (i, j) = (i as i64, j as i64);
+
+        let top = (1.0 - fx) * self.texel(i, j, wrap) + fx * self.texel(i + 1, j, wrap);
+        let bottom = (1.0 - fx) * self.texel(i, j + 1, wrap) + fx * self.texel(i + 1, j + 1, wrap);
+        (1.0 - fy) * top + fy * bottom
+    }
+
+    // Half the size in each direction, averaging blocks of up to 2x2 texels
+    fn downsample(&self) -> MipLevel {
+        let nx = (self.nx / 2).max(1);
+        let ny = (self.ny / 2).max(1);
+        let mut texels = Vec::with_capacity(nx * ny);
+
+        for j in 0..ny {
+            for i in 0..nx {
+                let mut sum = Vec3::new(0.0, 0.0, 0.0);
+                let mut count = 0.0;
+                for y in (2 * j)..(2 * j + 2).min(self.ny) {
+                    for x in (2 * i)..(2 * i + 2).min(self.nx) {
+                        sum += self.texels[x + self.nx * y];
+                        count += 1.0;
+                    }
+                }
+                texels.push(sum / count);
+            }
+        }
+
+        MipLevel { texels, nx, ny }
+    }
+}
+
+#[derive(Clone)]
+pub struct Image {
+    // Full size image first, then each level half the size of the last down
+    // to a single texel. Only the first is built unless filtering is
+    // trilinear.
+    levels: Vec<MipLevel>,
+    filter: Filter,
+    wrap: Wrap,
+    scale: (f32, f32),
+    offset: (f32, f32),
+}
+
 impl Image {
-    pub fn new(data: Vec<u8>, nx: usize, ny: usize) -> Self {
-        Image { data, nx, ny }
+    // `data` is 8 bit RGB, row by row from the top. It's converted to linear
+    // values up front so filtering blends light rather than encoded values.
+    // Panics if either size is zero.
+    pub fn new(data: Vec<u8>, nx: usize, ny: usize, color_space: ColorSpace) -> Self {
+        assert!(nx > 0 && ny > 0, "image has no pixels");
+        let texels = data
+            .chunks(3)
+            .map(|p| {
+                Vec3::new(
+                    color_space.decode(p[0]),
+                    color_space.decode(p[1]),
+                    color_space.decode(p[2]),
+                )
+            })
+            .collect();
+        Image {
+            levels: vec![MipLevel { texels, nx, ny }],
+            filter: Filter::default(),
+            wrap: Wrap::default(),
+            scale: (1.0, 1.0),
+            offset: (0.0, 0.0),
+        }
+    }
+
+    // Load a PPM, PNG or JPEG file
+    pub fn load(path: &Path, color_space: ColorSpace) -> Result<Self, ImageError> {
+        let image = decode_image(path)?;
+        Ok(Image::new(
+            image.data,
+            image.width,
+            image.height,
+            color_space,
+        ))
+    }
+
+    pub fn with_filter(mut self, filter: Filter) -> Self {
+        self.filter = filter;
+        self.levels.truncate(1);
+        if filter == Filter::Trilinear {
+            loop {
+                let last = self.levels.last().unwrap();
+                if last.nx == 1 && last.ny == 1 {
+                    break;
+                }
+                let next = last.downsample();
+                self.levels.push(next);
+            }
+        }
+        self
+    }
+
+    pub fn with_wrap(mut self, wrap: Wrap) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    // Texture coordinates are multiplied by `scale` and then moved by
+    // `offset`, so a scale of 4 tiles the image four times across
+    pub fn with_transform(mut self, scale: (f32, f32), offset: (f32, f32)) -> Self {
+        self.scale = scale;
+        self.offset = offset;
+        self
     }
 }
 
 impl Texture for Image {
-    fn value(&self, u: f32, v: f32, _p: Vec3) -> Vec3 {
-        let (nx, ny) = (self.nx, self.ny);
-        let mut i = (u * nx as f32) as usize;
-        let mut j = ((1.0 - v) * ny as f32) as usize;
-        if i > (nx - 1) {
-            i = nx - 1;
-        }
-        if j > (ny - 1) {
-            j = ny - 1;
+    fn value(&self, u: f32, v: f32, p: Vec3) -> Vec3 {
+        self.value_filtered(u, v, p, 0.0)
+    }
+
+    fn value_filtered(&self, u: f32, v: f32, _p: Vec3, footprint: f32) -> Vec3 {
+        let u = u * self.scale.0 + self.offset.0;
+        let v = v * self.scale.1 + self.offset.1;
+        let base = &self.levels[0];
+
+        match self.filter {
+            Filter::Nearest => base.nearest(u, v, self.wrap),
+            Filter::Bilinear => base.bilinear(u, v, self.wrap),
+            Filter::Trilinear => {
+                // The scale stretches the footprint across more texels too
+                let scale = self.scale.0.abs().max(self.scale.1.abs());
+                let texels = footprint * scale * base.nx.max(base.ny) as f32;
+                let max_level = (self.levels.len() - 1) as f32;
+                let level = texels.log2().clamp(0.0, max_level);
+
+                let lower = level.floor();
+                let t = level - lower;
+                let fine = self.levels[lower as usize].bilinear(u, v, self.wrap);
+                if t == 0.0 {
+                    return fine;
+                }
+                let coarse = self.levels[lower as usize + 1].bilinear(u, v, self.wrap);
+                (1.0 - t) * fine + t * coarse
+            }
         }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        color::ColorSpace,
+        material::{Filter, Image, Texture, Wrap},
+        vector::Vec3,
+    };
+
+    // Black and white texels side by side
+    fn image() -> Image {
+        Image::new(vec![0, 0, 0, 255, 255, 255], 2, 1, ColorSpace::Srgb)
+    }
+
+    #[test]
+    fn wrap_modes() {
+        let p = Vec3::new(0.0, 0.0, 0.0);
+        let black = Vec3::new(0.0, 0.0, 0.0);
+        let white = Vec3::new(1.0, 1.0, 1.0);
+
+        // Just past the right edge
+        assert_eq!(image().value(1.25, 0.5, p), white);
+        assert_eq!(image().with_wrap(Wrap::Repeat).value(1.25, 0.5, p), black);
+        assert_eq!(image().with_wrap(Wrap::Mirror).value(1.25, 0.5, p), white);
+        assert_eq!(image().with_wrap(Wrap::Mirror).value(1.75, 0.5, p), black);
+
+        // Scaling by two tiles the image twice
+        let tiled = image()
+            .with_wrap(Wrap::Repeat)
+            .with_transform((2.0, 1.0), (0.0, 0.0));
+        assert_eq!(tiled.value(0.3, 0.5, p), white);
+        assert_eq!(tiled.value(0.6, 0.5, p), black);
+    }
+
+    #[test]
+    fn filtering() {
+        let p = Vec3::new(0.0, 0.0, 0.0);
+        let grey = Vec3::new(0.5, 0.5, 0.5);
 
-        let ri = 3 * i + 3 * nx * j;
-        let gi = 3 * i + 3 * nx * j + 1;
-        let bi = 3 * i + 3 * nx * j + 2;
-        let r = self.data[ri] as f32 / 255.0;
-        let g = self.data[gi] as f32 / 255.0;
-        let b = self.data[bi] as f32 / 255.0;
+        // Halfway between the two texel centres
+        let bilinear = image().with_filter(Filter::Bilinear);
+        assert_eq!(bilinear.value(0.5, 0.5, p), grey);
+        assert_eq!(bilinear.value(0.25, 0.5, p), Vec3::new(0.0, 0.0, 0.0));
 
-        Vec3::new(r, g, b)
+        // A footprint covering the whole image averages it
+        let trilinear = image().with_filter(Filter::Trilinear);
+        assert_eq!(trilinear.value_filtered(0.25, 0.5, p, 1.0), grey);
+        assert_eq!(
+            trilinear.value_filtered(0.25, 0.5, p, 0.0),
+            Vec3::new(0.0, 0.0, 0.0)
+        );
     }
 }
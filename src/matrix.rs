@@ -0,0 +1,215 @@
+use serde::{Deserialize, Serialize};
+
+use std::ops::Mul;
+
+use crate::vector::Vec3;
+
+// 4x4 matrix for affine transforms, stored row by row. Points and vectors are
+// treated as columns so `a * b` applies `b` first.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(from = "[[f32; 4]; 4]", into = "[[f32; 4]; 4]")]
+pub struct Mat4 {
+    pub m: [[f32; 4]; 4],
+}
+
+impl Mat4 {
+    pub fn new(m: [[f32; 4]; 4]) -> Self {
+        Mat4 { m }
+    }
+
+    pub fn identity() -> Self {
+        Mat4::new([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn translation(offset: Vec3) -> Self {
+        Mat4::new([
+            [1.0, 0.0, 0.0, offset.x],
+            [0.0, 1.0, 0.0, offset.y],
+            [0.0, 0.0, 1.0, offset.z],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn scale(factor: Vec3) -> Self {
+        Mat4::new([
+            [factor.x, 0.0, 0.0, 0.0],
+            [0.0, factor.y, 0.0, 0.0],
+            [0.0, 0.0, factor.z, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    // Rotation of `angle` degrees about the x axis
+    pub fn rotation_x(angle: f32) -> Self {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        Mat4::new([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, cos, -sin, 0.0],
+            [0.0, sin, cos, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    // Rotation of `angle` degrees about the y axis
+    pub fn rotation_y(angle: f32) -> Self {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        Mat4::new([
+            [cos, 0.0, sin, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [-sin, 0.0, cos, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    // Rotation of `angle` degrees about the z axis
+    pub fn rotation_z(angle: f32) -> Self {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        Mat4::new([
+            [cos, -sin, 0.0, 0.0],
+            [sin, cos, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut t = [[0.0; 4]; 4];
+        for (i, row) in self.m.iter().enumerate() {
+            for (j, val) in row.iter().enumerate() {
+                t[j][i] = *val;
+            }
+        }
+        Mat4::new(t)
+    }
+
+    // Invert using Gauss-Jordan elimination with partial pivoting, returns
+    // `None` if the matrix is singular
+    pub fn inverse(&self) -> Option<Self> {
+        let mut a = self.m;
+        let mut inv = Mat4::identity().m;
+
+        for col in 0..4 {
+            let pivot = (col..4)
+                .max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))
+                .unwrap();
+            if a[pivot][col].abs() < 1e-12 {
+                return None;
+            }
+            a.swap(col, pivot);
+            inv.swap(col, pivot);
+
+            let scale = 1.0 / a[col][col];
+            for j in 0..4 {
+                a[col][j] *= scale;
+                inv[col][j] *= scale;
+            }
+
+            for row in 0..4 {
+                if row == col {
+                    continue;
+                }
+                let factor = a[row][col];
+                for j in 0..4 {
+                    a[row][j] -= factor * a[col][j];
+                    inv[row][j] -= factor * inv[col][j];
+                }
+            }
+        }
+
+        Some(Mat4::new(inv))
+    }
+
+    pub fn transform_point(&self, p: Vec3) -> Vec3 {
+        let m = &self.m;
+        let x = m[0][0] * p.x + m[0][1] * p.y + m[0][2] * p.z + m[0][3];
+        let y = m[1][0] * p.x + m[1][1] * p.y + m[1][2] * p.z + m[1][3];
+        let z = m[2][0] * p.x + m[2][1] * p.y + m[2][2] * p.z + m[2][3];
+        let w = m[3][0] * p.x + m[3][1] * p.y + m[3][2] * p.z + m[3][3];
+        if w == 1.0 {
+            Vec3::new(x, y, z)
+        } else {
+            Vec3::new(x, y, z) / w
+        }
+    }
+
+    // Transform a direction, ignoring any translation
+    pub fn transform_vector(&self, v: Vec3) -> Vec3 {
+        let m = &self.m;
+        Vec3::new(
+            m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+            m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+            m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z,
+        )
+    }
+}
+
+impl Mul for Mat4 {
+    type Output = Mat4;
+
+    fn mul(self, other: Mat4) -> Mat4 {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, val) in row.iter_mut().enumerate() {
+                *val = (0..4).map(|k| self.m[i][k] * other.m[k][j]).sum();
+            }
+        }
+        Mat4::new(m)
+    }
+}
+
+impl From<[[f32; 4]; 4]> for Mat4 {
+    fn from(m: [[f32; 4]; 4]) -> Mat4 {
+        Mat4::new(m)
+    }
+}
+
+impl From<Mat4> for [[f32; 4]; 4] {
+    fn from(m: Mat4) -> [[f32; 4]; 4] {
+        m.m
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{matrix::Mat4, vector::Vec3};
+
+    fn assert_close(a: Vec3, b: Vec3) {
+        assert!((a - b).get_mag() < 1e-4, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn inverse() {
+        let m = Mat4::translation(Vec3::new(1.0, -2.0, 3.0))
+            * Mat4::rotation_y(30.0)
+            * Mat4::scale(Vec3::new(2.0, 0.5, 4.0));
+        let inv = m.inverse().unwrap();
+
+        let p = Vec3::new(0.3, 1.7, -2.2);
+        assert_close(inv.transform_point(m.transform_point(p)), p);
+
+        let product = m * inv;
+        for (i, row) in product.m.iter().enumerate() {
+            for (j, val) in row.iter().enumerate() {
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert!((val - expected).abs() < 1e-5);
+            }
+        }
+
+        assert!(Mat4::scale(Vec3::new(1.0, 0.0, 1.0)).inverse().is_none());
+    }
+
+    #[test]
+    fn rotation() {
+        let x = Vec3::new(1.0, 0.0, 0.0);
+        let y = Vec3::new(0.0, 1.0, 0.0);
+        let z = Vec3::new(0.0, 0.0, 1.0);
+        assert_close(Mat4::rotation_x(90.0).transform_vector(y), z);
+        assert_close(Mat4::rotation_y(90.0).transform_vector(z), x);
+        assert_close(Mat4::rotation_z(90.0).transform_vector(x), y);
+    }
+}
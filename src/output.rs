@@ -0,0 +1,182 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use crate::{color::linear_to_srgb, vector::Vec3};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ImageFormat {
+    // Binary P6 PPM
+    Ppm,
+    // ASCII P3 PPM
+    PpmAscii,
+    Png,
+    // Portable float map, stores the raw linear floating point values
+    Pfm,
+}
+
+impl ImageFormat {
+    pub const NAMES: [&'static str; 4] = ["ppm", "ppm-ascii", "png", "pfm"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match &name.to_lowercase()[..] {
+            "ppm" => Some(ImageFormat::Ppm),
+            "ppm-ascii" => Some(ImageFormat::PpmAscii),
+            "png" => Some(ImageFormat::Png),
+            "pfm" => Some(ImageFormat::Pfm),
+            _ => None,
+        }
+    }
+
+    // Pick the format from a file's extension. ASCII PPMs share the `.ppm`
+    // extension so they can only be chosen by name.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "ppm-ascii" => None,
+            ext => ImageFormat::from_name(ext),
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Ppm | ImageFormat::PpmAscii => "ppm",
+            ImageFormat::Png => "png",
+            ImageFormat::Pfm => "pfm",
+        }
+    }
+}
+
+// Write the `width` by `height` linear colour values in `pixels`, stored top
+// row first, to `path`
+pub fn write_image(
+    path: &Path,
+    format: ImageFormat,
+    (width, height): (u64, u64),
+    pixels: &[Vec3],
+) -> io::Result<()> {
+    let mut output = BufWriter::new(File::create(path)?);
+
+    match format {
+        ImageFormat::Ppm => {
+            let header = format!("P6\n{} {}\n255\n", width, height);
+            output.write_all(header.as_bytes())?;
+            output.write_all(&to_rgb8_buffer(pixels))?;
+        }
+        ImageFormat::PpmAscii => {
+            let header = format!("P3\n{} {}\n255\n", width, height);
+            output.write_all(header.as_bytes())?;
+            for col in pixels {
+                let [r, g, b] = to_rgb8(*col);
+                let pixel = format!("{} {} {}\n", r, g, b);
+                output.write_all(pixel.as_bytes())?;
+            }
+        }
+        ImageFormat::Png => {
+            let mut encoder = png::Encoder::new(&mut output, width as u32, height as u32);
+            encoder.set_color(png::ColorType::Rgb);
+            encoder.set_depth(png::BitDepth::Eight);
+            let mut writer = encoder.write_header()?;
+            writer.write_image_data(&to_rgb8_buffer(pixels))?;
+            writer.finish()?;
+        }
+        ImageFormat::Pfm => {
+            // A negative scale marks the data as little endian
+            let header = format!("PF\n{} {}\n-1.0\n", width, height);
+            output.write_all(header.as_bytes())?;
+            // Rows are stored bottom to top
+            for row in pixels.chunks(width as usize).rev() {
+                for col in row {
+                    for channel in [col.x, col.y, col.z].iter() {
+                        output.write_all(&channel.to_le_bytes())?;
+                    }
+                }
+            }
+        }
+    }
+
+    output.flush()
+}
+
+fn to_rgb8_buffer(pixels: &[Vec3]) -> Vec<u8> {
+    pixels.iter().flat_map(|col| to_rgb8(*col)).collect()
+}
+
+// Apply the sRGB transfer function to a linear colour and quantise it to 8
+// bits per channel
+fn to_rgb8(col: Vec3) -> [u8; 3] {
+    // Float to int casts saturate so out of range values end up at 0 or 255
+    let encode = |val: f32| (linear_to_srgb(val) * 255.0).round() as u8;
+    [encode(col.x), encode(col.y), encode(col.z)]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{
+        material::decode::decode_image,
+        output::{write_image, ImageFormat},
+        vector::Vec3,
+    };
+
+    // Three by two so a mixed up width and height or row order shows up
+    fn pixels() -> Vec<Vec3> {
+        vec![
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+            Vec3::new(0.5, 0.25, 2.0),
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(-1.0, 1.0, 0.75),
+        ]
+    }
+
+    #[test]
+    fn round_trip() {
+        let dir = std::env::temp_dir().join(format!(
+            "simple-ray-tracer-output-test-{}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+
+        // 0.5, 0.25 and 0.75 go through the sRGB curve, out of range values
+        // are clamped
+        let expected = vec![
+            255, 0, 0, 0, 255, 0, 0, 0, 255, //
+            188, 137, 255, 0, 0, 0, 0, 255, 225,
+        ];
+        for (name, format) in [
+            ("binary.ppm", ImageFormat::Ppm),
+            ("ascii.ppm", ImageFormat::PpmAscii),
+            ("image.png", ImageFormat::Png),
+        ] {
+            let path = dir.join(name);
+            write_image(&path, format, (3, 2), &pixels()).unwrap();
+            let image = decode_image(&path).unwrap();
+            assert_eq!((image.width, image.height), (3, 2), "{}", name);
+            assert_eq!(image.data, expected, "{}", name);
+        }
+
+        let path = dir.join("image.pfm");
+        write_image(&path, ImageFormat::Pfm, (3, 2), &pixels()).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let header = b"PF\n3 2\n-1.0\n";
+        assert_eq!(&bytes[..header.len()], header);
+        let values: Vec<f32> = bytes[header.len()..]
+            .chunks(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        // The bottom row comes first, and the values aren't clamped
+        let rows = pixels();
+        let expected: Vec<f32> = rows[3..]
+            .iter()
+            .chain(&rows[..3])
+            .flat_map(|col| vec![col.x, col.y, col.z])
+            .collect();
+        assert_eq!(values, expected);
+    }
+}
@@ -0,0 +1,108 @@
+use crate::vector::Vec3;
+
+// Operators that squeeze the unbounded linear light of the render into the
+// 0 to 1 range of an ordinary image
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ToneMap {
+    // Leave the colour alone, anything too bright just clips
+    None,
+    Reinhard,
+    // Narkowicz's fit of the ACES filmic curve
+    Aces,
+}
+
+impl ToneMap {
+    pub const NAMES: [&'static str; 3] = ["none", "reinhard", "aces"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "none" => Some(ToneMap::None),
+            "reinhard" => Some(ToneMap::Reinhard),
+            "aces" => Some(ToneMap::Aces),
+            _ => None,
+        }
+    }
+
+    fn apply(self, val: f32) -> f32 {
+        match self {
+            ToneMap::None => val,
+            ToneMap::Reinhard => val / (1.0 + val),
+            ToneMap::Aces => {
+                let (a, b, c, d, e) = (2.51, 0.03, 2.43, 0.59, 0.14);
+                ((val * (a * val + b)) / (val * (c * val + d) + e)).clamp(0.0, 1.0)
+            }
+        }
+    }
+}
+
+// Adjustments made to each pixel once it has been rendered, in this order
+#[derive(Clone, Copy, Debug)]
+pub struct PostProcess {
+    // Multiplies the light reaching the camera
+    pub exposure: f32,
+    // Brightest value allowed in each channel, if any. Negative and NaN
+    // values are always removed.
+    pub clamp: Option<f32>,
+    pub tone_map: ToneMap,
+}
+
+impl Default for PostProcess {
+    fn default() -> Self {
+        PostProcess {
+            exposure: 1.0,
+            clamp: None,
+            tone_map: ToneMap::None,
+        }
+    }
+}
+
+impl PostProcess {
+    pub fn apply(&self, col: Vec3) -> Vec3 {
+        let channel = |val: f32| {
+            // `max` ignores NaN so this also gets rid of those
+            let val = (val * self.exposure).max(0.0);
+            let val = match self.clamp {
+                Some(max) => val.min(max),
+                None => val,
+            };
+            self.tone_map.apply(val)
+        };
+        Vec3::new(channel(col.x), channel(col.y), channel(col.z))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        post::{PostProcess, ToneMap},
+        vector::Vec3,
+    };
+
+    #[test]
+    fn post_process() {
+        let bright = Vec3::new(0.5, 4.0, f32::NAN);
+
+        let post = PostProcess {
+            exposure: 2.0,
+            clamp: Some(3.0),
+            tone_map: ToneMap::None,
+        };
+        assert_eq!(post.apply(bright), Vec3::new(1.0, 3.0, 0.0));
+
+        let reinhard = PostProcess {
+            tone_map: ToneMap::Reinhard,
+            ..PostProcess::default()
+        };
+        assert_eq!(reinhard.apply(bright), Vec3::new(0.5 / 1.5, 0.8, 0.0));
+
+        // ACES keeps black black and rolls very bright values off to white
+        let aces = PostProcess {
+            tone_map: ToneMap::Aces,
+            ..PostProcess::default()
+        };
+        let mapped = aces.apply(Vec3::new(0.0, 0.18, 1000.0));
+        assert!(mapped.x.abs() < 1e-6);
+        assert!(mapped.y > 0.1 && mapped.y < 0.5);
+        assert!((mapped.z - 1.0).abs() < 1e-6);
+    }
+}
@@ -8,15 +8,28 @@ pub struct Ray {
     pub origin: Vec3,
     pub dir: Vec3,
     pub time: f32,
+    // Wavelength of the light the ray carries in nanometres when rendering
+    // spectrally, `None` when it carries red, green and blue
+    pub wavelength: Option<f32>,
 }
 
 impl Ray {
     pub fn new(origin: Vec3, dir: Vec3, time: f32) -> Self {
-        Ray { origin, dir, time }
+        Ray {
+            origin,
+            dir,
+            time,
+            wavelength: None,
+        }
+    }
+
+    pub fn with_wavelength(mut self, wavelength: Option<f32>) -> Self {
+        self.wavelength = wavelength;
+        self
     }
 
     pub fn point_at_parameter(&self, t: f32) -> Vec3 {
-        self.origin.clone() + (t * self.dir.clone())
+        self.origin + (t * self.dir)
     }
 }
 
@@ -28,6 +41,12 @@ pub struct RayHit {
     pub point: Vec3,
     pub normal: Vec3,
     pub mat: Arc<dyn Material>,
+    // Texture coordinate units covered by one unit of distance along the
+    // surface, zero if the shape doesn't know
+    pub uv_scale: f32,
+    // Width of the area the ray covers in texture coordinates, used to pick
+    // a mip level. Zero means as sharp as possible.
+    pub footprint: f32,
 }
 
 impl RayHit {
@@ -39,6 +58,8 @@ impl RayHit {
             point,
             normal,
             mat,
+            uv_scale: 0.0,
+            footprint: 0.0,
         }
     }
 }
@@ -0,0 +1,444 @@
+use indicatif::ProgressBar;
+
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::channel,
+    },
+    thread,
+};
+
+use crate::{
+    color::luminance,
+    config::{Adaptive, RenderConfig},
+    film::{Film, FilmTile},
+    ray::{Ray, RayHit},
+    sampler::Sampler,
+    scene::Scene,
+    shapes::Hittable,
+    spectrum::{sample_wavelength, wavelength_weight},
+    utils::{hash_seed, seed_random},
+    vector::Vec3,
+};
+
+// Running totals of the samples taken for a pixel, kept between passes so
+// adaptive sampling can tell how noisy the pixel still is. The colours
+// themselves go to the film.
+#[derive(Clone, Copy, Debug)]
+pub struct PixelStats {
+    // Totals of each sample's luminance and its square, for the variance
+    luminance_sum: f64,
+    luminance_sq_sum: f64,
+    pub count: u64,
+}
+
+impl Default for PixelStats {
+    fn default() -> Self {
+        PixelStats {
+            luminance_sum: 0.0,
+            luminance_sq_sum: 0.0,
+            count: 0,
+        }
+    }
+}
+
+impl PixelStats {
+    fn add(&mut self, col: Vec3) {
+        let lum = luminance(col) as f64;
+        self.luminance_sum += lum;
+        self.luminance_sq_sum += lum * lum;
+        self.count += 1;
+    }
+
+    // Whether the standard error of the pixel's mean luminance has dropped
+    // below `threshold` relative to its brightness
+    fn converged(&self, adaptive: &Adaptive) -> bool {
+        if self.count < adaptive.min_samples.max(2) {
+            return false;
+        }
+        let n = self.count as f64;
+        let mean = self.luminance_sum / n;
+        let variance = ((self.luminance_sq_sum - n * mean * mean) / (n - 1.0)).max(0.0);
+        let error = (variance / n).sqrt();
+        // Very dark pixels are judged against a minimum brightness, otherwise
+        // noise that can't be seen would keep them sampling forever
+        error <= adaptive.threshold as f64 * mean.max(MIN_BRIGHTNESS)
+    }
+}
+
+const MIN_BRIGHTNESS: f64 = 0.05;
+
+// `spread` is how quickly the ray's footprint grows with distance, which is
+// only tracked for camera rays. Bounced rays pass zero and so see textures as
+// sharp as possible. `scatter_pdf` is the density the material the ray was
+// scattered from gave its direction, or `None` if it was specular and light
+// it finds can't also have been found by aiming at a light.
+fn color(
+    ray: Ray,
+    scene: &Scene,
+    config: &RenderConfig,
+    spread: f32,
+    depth: usize,
+    scatter_pdf: Option<f32>,
+    sampler: &mut dyn Sampler,
+) -> Vec3 {
+    let mut hit = match scene.world.hit(&ray, 0.001, f32::MAX) {
+        Some(hit) => hit,
+        None => return scene.background.color(ray),
+    };
+    hit.footprint = hit.t * spread * hit.uv_scale;
+
+    let mut emitted = hit.mat.emitted(hit.u, hit.v, hit.point);
+    if let Some(scatter_pdf) = scatter_pdf {
+        if config.sample_lights {
+            let light_pdf = scene.lights.pdf_value(&ray);
+            emitted *= power_heuristic(scatter_pdf, light_pdf);
+        }
+    }
+    if depth >= config.max_depth {
+        return emitted;
+    }
+
+    let bsdf = match hit.mat.sample(&ray, &hit, sampler) {
+        Some(bsdf) => bsdf,
+        None => return emitted,
+    };
+
+    let mut direct = Vec3::new(0.0, 0.0, 0.0);
+    // Decided by the material rather than this sample, since a specular layer
+    // being picked doesn't stop light reaching the others
+    if config.sample_lights && !scene.lights.is_empty() && !hit.mat.is_specular(&ray, &hit) {
+        direct = sample_light(&ray, &hit, scene, sampler);
+    }
+    // Nothing else can be found in a direction the material never scatters in
+    if bsdf.pdf == 0.0 {
+        return emitted + direct;
+    }
+
+    let scattered = Ray::new(hit.point, bsdf.dir, ray.time).with_wavelength(ray.wavelength);
+    let pdf = if bsdf.specular { None } else { Some(bsdf.pdf) };
+    let next = color(scattered, scene, config, 0.0, depth + 1, pdf, sampler);
+    emitted + direct + bsdf.value * next / bsdf.pdf
+}
+
+// Light reaching the camera from `hit` along a ray aimed at a random light.
+// Light found like this could also have been found by scattering, so it is
+// weighted to make the two add up to one.
+fn sample_light(ray: &Ray, hit: &RayHit, scene: &Scene, sampler: &mut dyn Sampler) -> Vec3 {
+    let none = Vec3::new(0.0, 0.0, 0.0);
+    let dir = scene.lights.random(hit.point, sampler);
+    let shadow = Ray::new(hit.point, dir, ray.time);
+
+    let light_hit = match scene.world.hit(&shadow, 0.001, f32::MAX) {
+        Some(light_hit) => light_hit,
+        None => return none,
+    };
+    let emitted = light_hit
+        .mat
+        .emitted(light_hit.u, light_hit.v, light_hit.point);
+    if emitted == none {
+        return none;
+    }
+
+    let light_pdf = scene.lights.pdf_value(&shadow);
+    let bsdf_pdf = hit.mat.pdf(ray, hit, dir);
+    if light_pdf == 0.0 || bsdf_pdf == 0.0 {
+        return none;
+    }
+    let weight = power_heuristic(light_pdf, bsdf_pdf);
+    hit.mat.eval(ray, hit, dir) * emitted * (weight / light_pdf)
+}
+
+// Veach's power heuristic, the weight given to a sample picked with density
+// `pdf` when `other_pdf` could also have picked it
+fn power_heuristic(pdf: f32, other_pdf: f32) -> f32 {
+    let (a, b) = (pdf * pdf, other_pdf * other_pdf);
+    if a + b == 0.0 {
+        return 0.0;
+    }
+    a / (a + b)
+}
+
+// Take up to `samples` more samples for every pixel, adding them to the
+// totals in `previous` and splatting them onto `film`. Pixels have fewer
+// taken once they've converged if adaptive sampling is on. The image is
+// rendered one scanline at a time spread across `threads` workers. Rows can
+// finish in any order so they are buffered here, then merged into the film
+// and passed to `write_row` from top to bottom.
+pub fn render<F>(
+    scene: &Scene,
+    config: &RenderConfig,
+    samples: u64,
+    previous: &[PixelStats],
+    film: &mut Film,
+    progress: &ProgressBar,
+    mut write_row: F,
+) where
+    F: FnMut(&[PixelStats]),
+{
+    let next_row = AtomicU64::new(0);
+    let (sender, receiver) = channel();
+
+    thread::scope(|scope| {
+        for _ in 0..config.threads {
+            let sender = sender.clone();
+            let next_row = &next_row;
+            scope.spawn(move || loop {
+                let i = next_row.fetch_add(1, Ordering::Relaxed);
+                if i >= config.height {
+                    break;
+                }
+                let start = (i * config.width) as usize;
+                let previous = &previous[start..start + config.width as usize];
+                let size = (config.width, config.height);
+                let tile = FilmTile::new(size, i, &config.filter);
+                let row = render_row(scene, config, samples, previous, tile, i);
+                if sender.send((i, row)).is_err() {
+                    break;
+                }
+            });
+        }
+        // Only the workers hold senders now so the loop below ends once
+        // they have all finished
+        drop(sender);
+
+        let mut finished = BTreeMap::new();
+        let mut next_to_write = 0;
+        for (i, row) in receiver.iter() {
+            finished.insert(i, row);
+            while let Some((row, tile)) = finished.remove(&next_to_write) {
+                film.merge(&tile);
+                write_row(&row);
+                progress.inc(1);
+                next_to_write += 1;
+            }
+        }
+    });
+}
+
+fn render_row(
+    scene: &Scene,
+    config: &RenderConfig,
+    s: u64,
+    previous: &[PixelStats],
+    mut tile: FilmTile,
+    i: u64,
+) -> (Vec<PixelStats>, FilmTile) {
+    let (x, y) = (config.width, config.height);
+    let mut row = previous.to_vec();
+    let spread = scene.camera.pixel_spread(y);
+    let mut sampler = config.sampler.build(config.seed, config.samples);
+
+    for (j, stats) in row.iter_mut().enumerate() {
+        // Each pixel gets its own numbers, picking up from however many
+        // samples it had before this pass, so the image doesn't depend on
+        // which thread renders which row
+        seed_random(hash_seed(config.seed, &[i, j as u64, stats.count]));
+
+        for _ in 0..s {
+            if let Some(adaptive) = &config.adaptive {
+                if stats.converged(adaptive) {
+                    break;
+                }
+            }
+
+            sampler.start_sample((j as u64, i), stats.count);
+            let (du, dv) = sampler.get_2d();
+            let pos = (j as f32 + du, i as f32 + dv);
+            let u = pos.0 / x as f32;
+            let v = 1.0 - pos.1 / y as f32;
+
+            let mut ray = scene.camera.get_ray(u, v, sampler.as_mut());
+            if config.spectral {
+                ray = ray.with_wavelength(Some(sample_wavelength(sampler.get_1d())));
+            }
+
+            let mut col = color(ray, scene, config, spread, 0, None, sampler.as_mut());
+            if let Some(wavelength) = ray.wavelength {
+                col = col * wavelength_weight(wavelength);
+            }
+            stats.add(col);
+            tile.add_sample(&config.filter, pos, col);
+        }
+    }
+
+    (row, tile)
+}
+
+#[cfg(test)]
+mod tests {
+    use indicatif::ProgressBar;
+
+    use std::path::PathBuf;
+
+    use crate::{
+        config::{Adaptive, RenderConfig},
+        film::{Film, FilterKind, PixelFilter},
+        output::ImageFormat,
+        post::PostProcess,
+        ray::Ray,
+        render::{color, render, PixelStats},
+        sampler::SamplerKind,
+        scene::{load_scene, SceneDescription},
+        utils::seed_random,
+        vector::Vec3,
+    };
+
+    fn config() -> RenderConfig {
+        RenderConfig {
+            width: 24,
+            height: 12,
+            scene: "spheres".to_owned(),
+            samples: 4,
+            adaptive: None,
+            sample_map: None,
+            pass_samples: None,
+            sampler: SamplerKind::Independent,
+            filter: PixelFilter::new(FilterKind::Mitchell),
+            max_depth: 10,
+            sample_lights: true,
+            spectral: false,
+            output: PathBuf::new(),
+            format: ImageFormat::Ppm,
+            seed: 0,
+            threads: 1,
+            export: None,
+            post: PostProcess::default(),
+        }
+    }
+
+    #[test]
+    fn convergence() {
+        let adaptive = Adaptive {
+            threshold: 0.05,
+            min_samples: 8,
+        };
+
+        // A flat colour converges as soon as it has the minimum samples
+        let mut flat = PixelStats::default();
+        for _ in 0..7 {
+            flat.add(Vec3::new(0.5, 0.5, 0.5));
+        }
+        assert!(!flat.converged(&adaptive));
+        flat.add(Vec3::new(0.5, 0.5, 0.5));
+        assert!(flat.converged(&adaptive));
+
+        // Alternating black and white needs many more
+        let mut noisy = PixelStats::default();
+        let mut count = 0;
+        while !noisy.converged(&adaptive) {
+            noisy.add(Vec3::new(1.0, 1.0, 1.0) * (count % 2) as f32);
+            count += 1;
+        }
+        assert!(count > 50);
+    }
+
+    #[test]
+    fn reproducible() {
+        let render_with = |threads: usize, seed: u64| {
+            let config = RenderConfig {
+                seed,
+                threads,
+                ..config()
+            };
+
+            seed_random(seed);
+            let scene = load_scene(&config.scene)
+                .unwrap()
+                .build(config.width, config.height)
+                .unwrap();
+
+            let previous = vec![PixelStats::default(); 24 * 12];
+            let mut film = Film::new(24, 12);
+            let progress = ProgressBar::hidden();
+            render(&scene, &config, 4, &previous, &mut film, &progress, |_| ());
+            film.image()
+        };
+
+        // Bit for bit the same however the rows are shared out, even with
+        // samples spilling over into neighbouring rows
+        let single = render_with(1, 7);
+        assert_eq!(single, render_with(4, 7));
+        assert_ne!(single, render_with(4, 8));
+    }
+
+    // Light reaching the floor of `json` directly below its lights, found
+    // with and without aiming rays at them. The two should agree.
+    fn direct_light(json: &str) -> (f64, f64) {
+        let description: SceneDescription = serde_json::from_str(json).unwrap();
+        let scene = description.build(1, 1).unwrap();
+
+        let mean = |sample_lights: bool| {
+            let config = RenderConfig {
+                max_depth: 1,
+                sample_lights,
+                ..config()
+            };
+            seed_random(3);
+            let mut sampler = SamplerKind::Independent.build(0, 1);
+            let n = 200_000;
+            let mut total = 0.0;
+            for _ in 0..n {
+                let ray = Ray::new(Vec3::new(0.3, 0.5, 0.2), Vec3::new(0.0, -1.0, 0.0), 0.0);
+                let col = color(ray, &scene, &config, 0.0, 0, None, sampler.as_mut());
+                total += col.x as f64;
+            }
+            total / n as f64
+        };
+        (mean(true), mean(false))
+    }
+
+    #[test]
+    fn stacked_lights() {
+        // One light right above another, so aiming at the top one finds the
+        // bottom one in the way
+        let (mis, scattered) = direct_light(
+            r#"{
+                "camera": { "look_from": [0, 4, 0], "look_at": [0, 0, 0], "up": [0, 0, 1], "vfov": 60 },
+                "background": { "color": [0, 0, 0] },
+                "materials": {
+                    "floor": { "type": "Lambertian", "albedo": 0.5 },
+                    "light": { "type": "DiffuseLight", "emit": 4 }
+                },
+                "objects": [
+                    { "type": "XZRect", "x0": -4, "x1": 4, "z0": -4, "z1": 4, "k": 0, "material": "floor" },
+                    { "type": "XZRect", "x0": -1, "x1": 1, "z0": -1, "z1": 1, "k": 1, "material": "light", "flip": true },
+                    { "type": "XZRect", "x0": -2, "x1": 2, "z0": -2, "z1": 2, "k": 2, "material": "light", "flip": true }
+                ]
+            }"#,
+        );
+        assert!(
+            (mis - scattered).abs() < 0.01 * scattered,
+            "{} {}",
+            mis,
+            scattered
+        );
+    }
+
+    #[test]
+    fn smooth_and_diffuse_layers() {
+        // A perfectly smooth glossy layer over a diffuse one. Picking the
+        // glossy layer mustn't stop the diffuse one seeing the light directly.
+        let (mis, scattered) = direct_light(
+            r#"{
+                "camera": { "look_from": [0, 4, 0], "look_at": [0, 0, 0], "up": [0, 0, 1], "vfov": 60 },
+                "background": { "color": [0, 0, 0] },
+                "materials": {
+                    "floor": { "type": "Principled", "base_color": 0.5, "roughness": 0 },
+                    "light": { "type": "DiffuseLight", "emit": 4 }
+                },
+                "objects": [
+                    { "type": "XZRect", "x0": -4, "x1": 4, "z0": -4, "z1": 4, "k": 0, "material": "floor" },
+                    { "type": "XZRect", "x0": -1, "x1": 1, "z0": -1, "z1": 1, "k": 1, "material": "light", "flip": true }
+                ]
+            }"#,
+        );
+        assert!(
+            (mis - scattered).abs() < 0.01 * scattered,
+            "{} {}",
+            mis,
+            scattered
+        );
+    }
+}
@@ -0,0 +1,362 @@
+use crate::utils::{gen_random, hash_seed};
+
+// Source of the numbers used to pick pixel offsets, lens positions, times and
+// scattering directions. Each sample of a pixel asks for its numbers in the
+// same order, one dimension after another, so samplers that spread their
+// points out evenly can do so separately in every dimension.
+pub trait Sampler {
+    // Begin the `index`th sample of `pixel`, going back to the first dimension
+    fn start_sample(&mut self, pixel: (u64, u64), index: u64);
+    fn get_1d(&mut self) -> f32;
+    fn get_2d(&mut self) -> (f32, f32);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SamplerKind {
+    Independent,
+    Stratified,
+    Halton,
+    Sobol,
+}
+
+impl SamplerKind {
+    pub const NAMES: [&'static str; 4] = ["independent", "stratified", "halton", "sobol"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "independent" => Some(SamplerKind::Independent),
+            "stratified" => Some(SamplerKind::Stratified),
+            "halton" => Some(SamplerKind::Halton),
+            "sobol" => Some(SamplerKind::Sobol),
+            _ => None,
+        }
+    }
+
+    // A new sampler for pixels taking up to `samples` samples each, with any
+    // randomisation driven by `seed`
+    pub fn build(self, seed: u64, samples: u64) -> Box<dyn Sampler> {
+        let state = SampleState {
+            seed,
+            samples,
+            pixel: (0, 0),
+            index: 0,
+            dimension: 0,
+        };
+        match self {
+            SamplerKind::Independent => Box::new(Independent),
+            SamplerKind::Stratified => Box::new(Stratified(state)),
+            SamplerKind::Halton => Box::new(Halton(state)),
+            SamplerKind::Sobol => Box::new(Sobol(state)),
+        }
+    }
+}
+
+// Plain uniform random numbers
+pub struct Independent;
+
+impl Sampler for Independent {
+    // The renderer already reseeds the random numbers for each pixel
+    fn start_sample(&mut self, _pixel: (u64, u64), _index: u64) {}
+
+    fn get_1d(&mut self) -> f32 {
+        gen_random()
+    }
+
+    fn get_2d(&mut self) -> (f32, f32) {
+        (gen_random(), gen_random())
+    }
+}
+
+// Where the other samplers are up to
+struct SampleState {
+    seed: u64,
+    samples: u64,
+    pixel: (u64, u64),
+    index: u64,
+    dimension: u64,
+}
+
+impl SampleState {
+    fn start(&mut self, pixel: (u64, u64), index: u64) {
+        self.pixel = pixel;
+        self.index = index;
+        self.dimension = 0;
+    }
+
+    // Move on to the next dimension, returning a hash that's fixed for this
+    // pixel and dimension but differs between them
+    fn next_dimension(&mut self) -> (u64, u32) {
+        let dimension = self.dimension;
+        self.dimension += 1;
+        let (x, y) = self.pixel;
+        let hash = hash_seed(self.seed, &[x, y, dimension]);
+        (dimension, hash as u32)
+    }
+
+    // The sample index shuffled for this pixel and dimension. Every dimension
+    // visits the points in a different order so they don't line up with each
+    // other.
+    fn shuffled_index(&self, hash: u32) -> u32 {
+        permute(self.index as u32, self.samples as u32, hash)
+    }
+}
+
+// Each dimension is split into one stratum per sample with a random point
+// taken in each. 2D samples use the largest grid with no more cells than
+// there are samples, and any samples left over are spread over the whole
+// square.
+struct Stratified(SampleState);
+
+impl Sampler for Stratified {
+    fn start_sample(&mut self, pixel: (u64, u64), index: u64) {
+        self.0.start(pixel, index);
+    }
+
+    fn get_1d(&mut self) -> f32 {
+        let (_, hash) = self.0.next_dimension();
+        let stratum = self.0.shuffled_index(hash);
+        below_one((stratum as f32 + gen_random()) / self.0.samples as f32)
+    }
+
+    fn get_2d(&mut self) -> (f32, f32) {
+        let (_, hash) = self.0.next_dimension();
+        let stratum = self.0.shuffled_index(hash);
+        // Cells that never got a sample would never be seen at all, so the
+        // grid can't have more cells than there are samples
+        let samples = self.0.samples as u32;
+        let nx = (samples as f32).sqrt().floor().max(1.0) as u32;
+        let ny = (samples / nx).max(1);
+        if stratum >= nx * ny {
+            return (gen_random(), gen_random());
+        }
+        let (i, j) = (stratum % nx, stratum / nx);
+        (
+            below_one((i as f32 + gen_random()) / nx as f32),
+            below_one((j as f32 + gen_random()) / ny as f32),
+        )
+    }
+}
+
+// The Halton sequence, with each pixel's points shifted by a random amount
+// in every dimension so neighbouring pixels don't share the same pattern
+struct Halton(SampleState);
+
+impl Halton {
+    fn sample(&self, dimension: u64, hash: u32) -> f32 {
+        match PRIMES.get(dimension as usize) {
+            Some(&base) => {
+                let shift = hash as f32 / 4_294_967_296.0;
+                let val = radical_inverse(base, self.0.index) + shift;
+                val - val.floor()
+            }
+            // Past the end of the table the points stop being much better
+            // than random ones anyway
+            None => gen_random(),
+        }
+    }
+}
+
+impl Sampler for Halton {
+    fn start_sample(&mut self, pixel: (u64, u64), index: u64) {
+        self.0.start(pixel, index);
+    }
+
+    fn get_1d(&mut self) -> f32 {
+        let (dimension, hash) = self.0.next_dimension();
+        self.sample(dimension, hash)
+    }
+
+    fn get_2d(&mut self) -> (f32, f32) {
+        // Two dimensions with their own bases
+        let (first, hash_0) = self.0.next_dimension();
+        let (second, hash_1) = self.0.next_dimension();
+        (self.sample(first, hash_0), self.sample(second, hash_1))
+    }
+}
+
+// The first two dimensions of the Sobol sequence, which form a (0, 2)
+// sequence. Higher dimensions are "padded" from copies of these with the
+// points shuffled and XOR scrambled differently for each dimension and pixel.
+struct Sobol(SampleState);
+
+impl Sampler for Sobol {
+    fn start_sample(&mut self, pixel: (u64, u64), index: u64) {
+        self.0.start(pixel, index);
+    }
+
+    fn get_1d(&mut self) -> f32 {
+        let (_, hash) = self.0.next_dimension();
+        let index = self.0.shuffled_index(hash);
+        let scramble = hash_seed(hash as u64, &[1]) as u32;
+        to_unit(index.reverse_bits() ^ scramble)
+    }
+
+    fn get_2d(&mut self) -> (f32, f32) {
+        let (_, hash) = self.0.next_dimension();
+        let index = self.0.shuffled_index(hash);
+        let scramble = hash_seed(hash as u64, &[2]);
+        (
+            to_unit(index.reverse_bits() ^ scramble as u32),
+            to_unit(sobol_second(index) ^ (scramble >> 32) as u32),
+        )
+    }
+}
+
+// Second dimension of the Sobol sequence as a 32 bit fraction
+fn sobol_second(mut index: u32) -> u32 {
+    let mut v = 1 << 31;
+    let mut result = 0;
+    while index != 0 {
+        if index & 1 == 1 {
+            result ^= v;
+        }
+        index >>= 1;
+        v ^= v >> 1;
+    }
+    result
+}
+
+// Turn a 32 bit fraction into a float below one. Only the top 24 bits fit in
+// an f32 so the rest are dropped rather than letting it round up to one.
+fn to_unit(bits: u32) -> f32 {
+    (bits >> 8) as f32 / (1 << 24) as f32
+}
+
+// Mirror the digits of `index` in `base` around the decimal point
+fn radical_inverse(base: u64, mut index: u64) -> f32 {
+    let inv_base = 1.0 / base as f64;
+    let mut scale = inv_base;
+    let mut result = 0.0;
+    while index > 0 {
+        result += (index % base) as f64 * scale;
+        index /= base;
+        scale *= inv_base;
+    }
+    below_one(result as f32)
+}
+
+// Rounding can push values a whisker under one up to it, so pull them back
+fn below_one(val: f32) -> f32 {
+    val.min(1.0 - f32::EPSILON)
+}
+
+// Kensler's hash based permutation, maps `i` to a different element of
+// `0..len` for each value of `hash` without having to store a table
+fn permute(mut i: u32, len: u32, hash: u32) -> u32 {
+    if len <= 1 {
+        return 0;
+    }
+    let mut w = len - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+    loop {
+        i ^= hash;
+        i = i.wrapping_mul(0xe170893d);
+        i ^= hash >> 16;
+        i ^= (i & w) >> 4;
+        i ^= hash >> 8;
+        i = i.wrapping_mul(0x0929eb3f);
+        i ^= hash >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | hash >> 27);
+        i = i.wrapping_mul(0x6935fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dcb303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e501cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860a3df);
+        i &= w;
+        i ^= i >> 5;
+        if i < len {
+            break;
+        }
+    }
+    (i.wrapping_add(hash)) % len
+}
+
+const PRIMES: [u64; 32] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131,
+];
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        sampler::{permute, SamplerKind},
+        utils::seed_random,
+    };
+
+    #[test]
+    fn permutation() {
+        for len in [1, 5, 16, 100] {
+            let mut seen: Vec<u32> = (0..len).map(|i| permute(i, len, 12345)).collect();
+            seen.sort_unstable();
+            assert_eq!(seen, (0..len).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn stratification() {
+        // With 16 samples the stratified and Sobol samplers should put one
+        // point in each sixteenth of a 1D dimension, and in each cell of a 4x4
+        // grid in 2D. Everything should stay below one.
+        let n = 16;
+        for name in SamplerKind::NAMES.iter() {
+            let kind = SamplerKind::from_name(name).unwrap();
+            let mut sampler = kind.build(3, n);
+            let mut cells = [0; 16];
+            let mut strata = [[0; 16]; 2];
+            for index in 0..n {
+                sampler.start_sample((5, 7), index);
+                let (a, b) = sampler.get_2d();
+                let c = sampler.get_1d();
+                let d = sampler.get_1d();
+                for val in [a, b, c, d].iter() {
+                    assert!((0.0..1.0).contains(val), "{} gave {}", name, val);
+                }
+                cells[(a * 4.0) as usize + 4 * (b * 4.0) as usize] += 1;
+                strata[0][(c * 16.0) as usize] += 1;
+                strata[1][(d * 16.0) as usize] += 1;
+            }
+
+            if kind == SamplerKind::Stratified || kind == SamplerKind::Sobol {
+                assert!(cells.iter().all(|&count| count == 1), "{}", name);
+                for dim in strata.iter() {
+                    assert!(dim.iter().all(|&count| count == 1), "{}", name);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn uneven_sample_counts() {
+        // 50 samples don't make a square grid, but the whole square should
+        // still be covered evenly
+        seed_random(4);
+        let n = 50;
+        for name in SamplerKind::NAMES.iter() {
+            let mut sampler = SamplerKind::from_name(name).unwrap().build(9, n);
+            let mut cells = [0; 100];
+            let mut sum = (0.0, 0.0);
+            let pixels = 40;
+            for pixel in 0..pixels {
+                for index in 0..n {
+                    sampler.start_sample((pixel, 0), index);
+                    let (a, b) = sampler.get_2d();
+                    cells[(a * 10.0) as usize + 10 * (b * 10.0) as usize] += 1;
+                    sum.0 += a as f64;
+                    sum.1 += b as f64;
+                }
+            }
+
+            let count = (pixels * n) as f64;
+            assert!((sum.0 / count - 0.5).abs() < 0.01, "{}", name);
+            assert!((sum.1 / count - 0.5).abs() < 0.01, "{}", name);
+            assert!(cells.iter().all(|&c| c > 5), "{} {:?}", name, cells);
+        }
+    }
+}
@@ -0,0 +1,1041 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    material::{Absorption, ConductorPreset, Dispersion},
+    scene::{
+        description::{
+            CameraDescription, ConductorIor, ImageOptions, MaterialDescription, ObjectDescription,
+            SceneDescription, TextureDescription, TextureRef,
+        },
+        Background,
+    },
+    utils::gen_random,
+    vector::Vec3,
+};
+
+pub const NAMES: [&str; 13] = [
+    "default",
+    "spheres",
+    "motion",
+    "textures",
+    "perlin",
+    "image",
+    "light",
+    "cornell",
+    "cornell-smoke",
+    "microfacet",
+    "principled",
+    "glass",
+    "prism",
+];
+
+pub fn scene(scene_name: &str) -> Option<SceneDescription> {
+    let description = match scene_name {
+        "default" => default_scene(),
+        "spheres" => spheres_scene(),
+        "motion" => motion_blur(),
+        "textures" => textures_scene(),
+        "perlin" => perlin_scene(),
+        "image" => test_image_scene(),
+        "light" => light_scene(),
+        "cornell" => cornell_box(),
+        "cornell-smoke" => cornell_smoke(),
+        "microfacet" => microfacet_scene(),
+        "principled" => principled_scene(),
+        "glass" => glass_scene(),
+        "prism" => prism_scene(),
+        _ => return None,
+    };
+    Some(description)
+}
+
+// Convenience functions to keep the scenes below readable
+
+fn camera(from: Vec3, to: Vec3, vfov: f32, aperture: f32) -> CameraDescription {
+    CameraDescription {
+        look_from: from,
+        look_at: to,
+        vfov,
+        aperture,
+        ..CameraDescription::default()
+    }
+}
+
+fn sphere(center: Vec3, radius: f32, material: &str) -> ObjectDescription {
+    ObjectDescription::Sphere {
+        center,
+        radius,
+        material: material.to_owned(),
+    }
+}
+
+fn materials(list: Vec<(&str, MaterialDescription)>) -> BTreeMap<String, MaterialDescription> {
+    list.into_iter()
+        .map(|(name, mat)| (name.to_owned(), mat))
+        .collect()
+}
+
+fn default_scene() -> SceneDescription {
+    let materials = materials(vec![
+        (
+            "mat_one",
+            MaterialDescription::Lambertian {
+                albedo: [0.8, 0.3, 0.2].into(),
+            },
+        ),
+        (
+            "mat_two",
+            MaterialDescription::Lambertian {
+                albedo: [0.5, 0.4, 0.1].into(),
+            },
+        ),
+        (
+            "mat_three",
+            MaterialDescription::Metal {
+                albedo: [0.3, 0.2, 0.8].into(),
+                fuzz: 0.1,
+            },
+        ),
+        (
+            "mat_four",
+            MaterialDescription::Dielectric {
+                refractive_index: 1.5,
+                absorption: None,
+                surface_color: None,
+                dispersion: None,
+            },
+        ),
+    ]);
+
+    let objects = vec![
+        sphere(Vec3::new(0.0, 0.0, -1.0), 0.5, "mat_one"),
+        sphere(Vec3::new(0.0, -100.5, -1.0), 100.0, "mat_two"),
+        sphere(Vec3::new(1.0, 0.0, -1.0), 0.5, "mat_three"),
+        sphere(Vec3::new(-1.0, 0.0, -1.0), 0.5, "mat_four"),
+    ];
+
+    // Camera setup
+    let from = Vec3::new(-2.0, 2.0, 1.0);
+    let to = Vec3::new(0.0, 0.0, -1.0);
+    let aperture = 0.5;
+
+    SceneDescription {
+        camera: camera(from, to, 50.0, aperture),
+        materials,
+        objects,
+        ..SceneDescription::default()
+    }
+}
+
+fn spheres_scene() -> SceneDescription {
+    let mut materials = materials(vec![
+        (
+            "ground",
+            MaterialDescription::Lambertian {
+                albedo: [0.8, 0.3, 0.2].into(),
+            },
+        ),
+        (
+            "mat_one",
+            MaterialDescription::Metal {
+                albedo: [0.3, 0.2, 0.8].into(),
+                fuzz: 0.05,
+            },
+        ),
+        (
+            "mat_two",
+            MaterialDescription::Metal {
+                albedo: [0.6, 0.9, 0.6].into(),
+                fuzz: 0.2,
+            },
+        ),
+        (
+            "mat_three",
+            MaterialDescription::Dielectric {
+                refractive_index: 1.5,
+                absorption: None,
+                surface_color: None,
+                dispersion: None,
+            },
+        ),
+    ]);
+    let mut objects = vec![sphere(Vec3::new(0.0, -1000.0, 0.0), 1000.0, "ground")];
+
+    let collisions = vec![
+        (Vec3::new(-3.0, 0.6, 1.5), 0.6),
+        (Vec3::new(-4.0, 1.4, -2.0), 1.4),
+        (Vec3::new(2.0, 0.5, -2.0), 0.5),
+    ];
+
+    objects.push(sphere(collisions[0].0, collisions[0].1, "mat_one"));
+    // Big boi
+    objects.push(sphere(collisions[1].0, collisions[1].1, "mat_two"));
+    objects.push(sphere(collisions[2].0, collisions[2].1, "mat_three"));
+
+    // Convenience function to make sure a new postion and radius won't collide
+    // with the given Vec of spheres
+    fn check_for_collision(center: Vec3, radius: f32, collisions: &[(Vec3, f32)]) -> bool {
+        for sphere in collisions.iter() {
+            let a = (sphere.0 - center).get_mag();
+            let b = sphere.1 + radius;
+            if a < b {
+                return true;
+            }
+        }
+        false
+    }
+
+    for x in -11..11 {
+        for z in -11..11 {
+            let radius = gen_random() / 5.0;
+            let mat_chance = gen_random();
+
+            let center = Vec3::new(
+                x as f32 + 0.25 + gen_random() / 2.0,
+                radius,
+                z as f32 + 0.25 + gen_random() / 2.0,
+            );
+
+            if check_for_collision(center, radius, &collisions) {
+                continue;
+            }
+            if gen_random() < 0.8 {
+                let mat = match mat_chance {
+                    val if val < 0.6 => {
+                        // Diffuse
+                        MaterialDescription::Lambertian {
+                            albedo: [gen_random(), gen_random(), gen_random()].into(),
+                        }
+                    }
+                    val if val < 0.85 => {
+                        // Metallic
+                        MaterialDescription::Metal {
+                            albedo: [gen_random(), gen_random(), gen_random()].into(),
+                            fuzz: gen_random(),
+                        }
+                    }
+                    _ => {
+                        // Glass
+                        MaterialDescription::Dielectric {
+                            refractive_index: gen_random(),
+                            absorption: None,
+                            surface_color: None,
+                            dispersion: None,
+                        }
+                    }
+                };
+                // Each small sphere gets its own material
+                let name = format!("sphere_{}", objects.len());
+                objects.push(sphere(center, radius, &name));
+                materials.insert(name, mat);
+            }
+        }
+    }
+
+    let from = Vec3::new(3.0, 1.5, 2.0);
+    let to = Vec3::new(0.0, 0.0, -1.0);
+    let aperture = 0.05;
+
+    SceneDescription {
+        camera: camera(from, to, 50.0, aperture),
+        materials,
+        objects,
+        ..SceneDescription::default()
+    }
+}
+
+fn motion_blur() -> SceneDescription {
+    let materials = materials(vec![
+        (
+            "ground",
+            MaterialDescription::Lambertian {
+                albedo: [0.5, 0.5, 0.5].into(),
+            },
+        ),
+        (
+            "mat_one",
+            MaterialDescription::Lambertian {
+                albedo: [0.8, 0.2, 0.2].into(),
+            },
+        ),
+        (
+            "mat_two",
+            MaterialDescription::Lambertian {
+                albedo: [0.2, 0.8, 0.2].into(),
+            },
+        ),
+        (
+            "mat_three",
+            MaterialDescription::Lambertian {
+                albedo: [0.2, 0.2, 0.8].into(),
+            },
+        ),
+    ]);
+
+    let sphere_two = ObjectDescription::MSphere {
+        center_0: Vec3::new(0.0, 0.75, -1.0),
+        center_1: Vec3::new(0.0, 0.5, -1.0),
+        radius: 0.5,
+        time_0: 0.0,
+        time_1: 1.0,
+        material: "mat_two".to_owned(),
+    };
+    let sphere_three = ObjectDescription::MSphere {
+        center_0: Vec3::new(1.25, 1.0, -1.0),
+        center_1: Vec3::new(1.25, 0.5, -1.0),
+        radius: 0.5,
+        time_0: 0.0,
+        time_1: 1.0,
+        material: "mat_three".to_owned(),
+    };
+
+    let objects = vec![
+        sphere(Vec3::new(0.0, -500.0, -1.0), 500.0, "ground"),
+        sphere(Vec3::new(-1.25, 0.5, -1.0), 0.5, "mat_one"),
+        sphere_two,
+        sphere_three,
+    ];
+
+    let from = Vec3::new(0.0, 0.5, 2.0);
+    let to = Vec3::new(0.0, 0.3, -1.0);
+    let aperture = 0.0;
+
+    SceneDescription {
+        camera: CameraDescription {
+            time_0: 0.0,
+            time_1: 1.0,
+            ..camera(from, to, 70.0, aperture)
+        },
+        materials,
+        objects,
+        ..SceneDescription::default()
+    }
+}
+
+fn textures_scene() -> SceneDescription {
+    let mut textures = BTreeMap::new();
+    textures.insert(
+        "ground".to_owned(),
+        TextureDescription::Checkered {
+            odd: [0.35, 0.35, 0.45].into(),
+            even: [0.5, 0.5, 0.6].into(),
+        },
+    );
+    textures.insert(
+        "red_green".to_owned(),
+        TextureDescription::Checkered {
+            odd: [0.8, 0.2, 0.2].into(),
+            even: [0.2, 0.8, 0.2].into(),
+        },
+    );
+    textures.insert(
+        "black_white".to_owned(),
+        TextureDescription::Checkered {
+            odd: [0.0, 0.0, 0.0].into(),
+            even: [1.0, 1.0, 1.0].into(),
+        },
+    );
+
+    let materials = materials(vec![
+        (
+            "ground",
+            MaterialDescription::Lambertian {
+                albedo: "ground".into(),
+            },
+        ),
+        (
+            "mat_metal",
+            MaterialDescription::Metal {
+                albedo: [0.8, 0.8, 0.8].into(),
+                fuzz: 0.0,
+            },
+        ),
+        (
+            "check_metal",
+            MaterialDescription::Metal {
+                albedo: "red_green".into(),
+                fuzz: 0.0,
+            },
+        ),
+        (
+            "check_lam",
+            MaterialDescription::Lambertian {
+                albedo: "black_white".into(),
+            },
+        ),
+    ]);
+
+    let objects = vec![
+        sphere(Vec3::new(0.0, -500.0, -1.0), 500.0, "ground"),
+        sphere(Vec3::new(0.0, 0.8, -1.2), 0.8, "mat_metal"),
+        sphere(Vec3::new(1.8, 0.5, -0.8), 0.5, "check_metal"),
+        sphere(Vec3::new(-1.8, 0.5, -0.8), 0.5, "check_lam"),
+    ];
+
+    // Camera setup
+    let from = Vec3::new(0.0, 1.0, 1.5);
+    let to = Vec3::new(0.0, 0.0, -1.0);
+    let aperture = 0.0;
+
+    SceneDescription {
+        camera: camera(from, to, 70.0, aperture),
+        textures,
+        materials,
+        objects,
+        ..SceneDescription::default()
+    }
+}
+
+fn perlin_scene() -> SceneDescription {
+    let mut textures = BTreeMap::new();
+    textures.insert("noise".to_owned(), TextureDescription::Noise { scale: 4.0 });
+
+    let materials = materials(vec![(
+        "noise",
+        MaterialDescription::Lambertian {
+            albedo: "noise".into(),
+        },
+    )]);
+
+    let objects = vec![
+        sphere(Vec3::new(0.0, 2.0, -1.0), 2.0, "noise"),
+        sphere(Vec3::new(0.0, -500.0, -1.0), 500.0, "noise"),
+    ];
+
+    // Camera setup
+    let from = Vec3::new(-7.0, 3.2, 1.0);
+    let to = Vec3::new(0.0, 0.0, -1.0);
+    let aperture = 0.0;
+
+    SceneDescription {
+        camera: camera(from, to, 60.0, aperture),
+        textures,
+        materials,
+        objects,
+        ..SceneDescription::default()
+    }
+}
+
+fn test_image_scene() -> SceneDescription {
+    #[rustfmt::skip]
+    let data = vec![
+        // Placeholder colour values for proper image data
+        // Red band at top
+        255, 0, 0,
+        215, 0, 0,
+        175, 0, 0,
+        135, 0, 0,
+        // Green band
+        0, 255, 0,
+        0, 215, 0,
+        0, 175, 0,
+        0, 135, 0,
+        // Blue band
+        0, 0, 255,
+        0, 0, 215,
+        0, 0, 175,
+        0, 0, 135,
+        // Yellow band at bottom
+        255, 255, 0,
+        215, 215, 0,
+        175, 175, 0,
+        135, 135, 0,
+    ];
+    let (width, height) = (4, 4);
+
+    let mut textures = BTreeMap::new();
+    textures.insert(
+        "bands".to_owned(),
+        TextureDescription::Image {
+            width,
+            height,
+            data,
+            options: ImageOptions::default(),
+        },
+    );
+
+    let materials = materials(vec![
+        (
+            "mat_one",
+            MaterialDescription::Lambertian {
+                albedo: "bands".into(),
+            },
+        ),
+        (
+            "mat_two",
+            MaterialDescription::Lambertian {
+                albedo: [0.4, 0.4, 0.5].into(),
+            },
+        ),
+    ]);
+
+    let objects = vec![
+        sphere(Vec3::new(0.0, 1.5, -1.0), 1.5, "mat_one"),
+        sphere(Vec3::new(0.0, -500.0, -1.0), 500.0, "mat_two"),
+    ];
+
+    // Camera setup
+    let from = Vec3::new(-7.0, 3.2, 1.0);
+    let to = Vec3::new(0.0, 0.0, -1.0);
+    let aperture = 0.0;
+
+    SceneDescription {
+        camera: camera(from, to, 60.0, aperture),
+        textures,
+        materials,
+        objects,
+        ..SceneDescription::default()
+    }
+}
+
+fn light_scene() -> SceneDescription {
+    let mut textures = BTreeMap::new();
+    textures.insert("noise".to_owned(), TextureDescription::Noise { scale: 4.0 });
+
+    let materials = materials(vec![
+        (
+            "noise",
+            MaterialDescription::Lambertian {
+                albedo: "noise".into(),
+            },
+        ),
+        (
+            "light",
+            MaterialDescription::DiffuseLight {
+                emit: [4.0, 4.0, 4.0].into(),
+            },
+        ),
+    ]);
+
+    let objects = vec![
+        sphere(Vec3::new(0.0, 2.0, -1.0), 2.0, "noise"),
+        sphere(Vec3::new(0.0, -500.0, -1.0), 500.0, "noise"),
+        sphere(Vec3::new(2.0, 5.5, 1.0), 1.0, "light"),
+    ];
+
+    // Camera setup
+    let from = Vec3::new(-7.0, 3.2, 1.0);
+    let to = Vec3::new(0.0, 1.0, -1.0);
+    let aperture = 0.0;
+
+    SceneDescription {
+        camera: camera(from, to, 60.0, aperture),
+        // Only the light illuminates the scene
+        background: Background::Color(Vec3::new(0.0, 0.0, 0.0)),
+        textures,
+        materials,
+        objects,
+        ..SceneDescription::default()
+    }
+}
+
+// The standard Cornell box, best rendered with a square image
+fn cornell_box() -> SceneDescription {
+    // A white box of the given size turned about its corner and moved into
+    // place
+    fn placed_box(size: Vec3, angle: f32, offset: Vec3) -> ObjectDescription {
+        let cuboid = ObjectDescription::Cuboid {
+            min: Vec3::new(0.0, 0.0, 0.0),
+            max: size,
+            material: "white".to_owned(),
+        };
+        let rotated = ObjectDescription::RotateY {
+            angle,
+            object: Box::new(cuboid),
+        };
+        ObjectDescription::Translate {
+            offset,
+            object: Box::new(rotated),
+        }
+    }
+
+    let materials = materials(vec![
+        (
+            "red",
+            MaterialDescription::Lambertian {
+                albedo: [0.65, 0.05, 0.05].into(),
+            },
+        ),
+        (
+            "white",
+            MaterialDescription::Lambertian {
+                albedo: [0.73, 0.73, 0.73].into(),
+            },
+        ),
+        (
+            "green",
+            MaterialDescription::Lambertian {
+                albedo: [0.12, 0.45, 0.15].into(),
+            },
+        ),
+        (
+            "light",
+            MaterialDescription::DiffuseLight {
+                emit: [15.0, 15.0, 15.0].into(),
+            },
+        ),
+    ]);
+
+    // Walls all face into the box
+    let objects = vec![
+        ObjectDescription::YZRect {
+            y0: 0.0,
+            y1: 555.0,
+            z0: 0.0,
+            z1: 555.0,
+            k: 555.0,
+            material: "green".to_owned(),
+            flip: true,
+        },
+        ObjectDescription::YZRect {
+            y0: 0.0,
+            y1: 555.0,
+            z0: 0.0,
+            z1: 555.0,
+            k: 0.0,
+            material: "red".to_owned(),
+            flip: false,
+        },
+        ObjectDescription::XZRect {
+            x0: 213.0,
+            x1: 343.0,
+            z0: 227.0,
+            z1: 332.0,
+            k: 554.0,
+            material: "light".to_owned(),
+            flip: true,
+        },
+        // Ceiling
+        ObjectDescription::XZRect {
+            x0: 0.0,
+            x1: 555.0,
+            z0: 0.0,
+            z1: 555.0,
+            k: 555.0,
+            material: "white".to_owned(),
+            flip: true,
+        },
+        // Floor
+        ObjectDescription::XZRect {
+            x0: 0.0,
+            x1: 555.0,
+            z0: 0.0,
+            z1: 555.0,
+            k: 0.0,
+            material: "white".to_owned(),
+            flip: false,
+        },
+        // Back wall
+        ObjectDescription::XYRect {
+            x0: 0.0,
+            x1: 555.0,
+            y0: 0.0,
+            y1: 555.0,
+            k: 555.0,
+            material: "white".to_owned(),
+            flip: true,
+        },
+        placed_box(
+            Vec3::new(165.0, 330.0, 165.0),
+            15.0,
+            Vec3::new(265.0, 0.0, 295.0),
+        ),
+        placed_box(
+            Vec3::new(165.0, 165.0, 165.0),
+            -18.0,
+            Vec3::new(130.0, 0.0, 65.0),
+        ),
+    ];
+
+    // Camera setup
+    let from = Vec3::new(278.0, 278.0, -800.0);
+    let to = Vec3::new(278.0, 278.0, 0.0);
+    let aperture = 0.0;
+
+    SceneDescription {
+        camera: camera(from, to, 40.0, aperture),
+        background: Background::Color(Vec3::new(0.0, 0.0, 0.0)),
+        materials,
+        objects,
+        ..SceneDescription::default()
+    }
+}
+
+// The Cornell box with its two boxes replaced by blocks of dark smoke and
+// white fog
+fn cornell_smoke() -> SceneDescription {
+    let mut description = cornell_box();
+
+    description.materials.insert(
+        "smoke".to_owned(),
+        MaterialDescription::Isotropic {
+            albedo: [0.0, 0.0, 0.0].into(),
+        },
+    );
+    description.materials.insert(
+        "fog".to_owned(),
+        MaterialDescription::Isotropic {
+            albedo: [1.0, 1.0, 1.0].into(),
+        },
+    );
+
+    // The boxes are the last two objects
+    let fog_box = description.objects.pop().unwrap();
+    let smoke_box = description.objects.pop().unwrap();
+    description.objects.push(ObjectDescription::ConstantMedium {
+        boundary: Box::new(smoke_box),
+        density: 0.01,
+        material: "smoke".to_owned(),
+    });
+    description.objects.push(ObjectDescription::ConstantMedium {
+        boundary: Box::new(fog_box),
+        density: 0.01,
+        material: "fog".to_owned(),
+    });
+
+    description
+}
+
+// Rough metals and glass lit by a single light
+fn microfacet_scene() -> SceneDescription {
+    fn conductor(preset: ConductorPreset, roughness: TextureRef) -> MaterialDescription {
+        MaterialDescription::Conductor {
+            ior: ConductorIor::Preset { preset },
+            roughness,
+        }
+    }
+
+    let mut textures = BTreeMap::new();
+    textures.insert(
+        "ground".to_owned(),
+        TextureDescription::Checkered {
+            odd: [0.35, 0.35, 0.35].into(),
+            even: [0.6, 0.6, 0.6].into(),
+        },
+    );
+    // Patches of polished and brushed metal
+    textures.insert(
+        "patchy".to_owned(),
+        TextureDescription::Checkered {
+            odd: 0.05.into(),
+            even: 0.5.into(),
+        },
+    );
+
+    let materials = materials(vec![
+        (
+            "ground",
+            MaterialDescription::Lambertian {
+                albedo: "ground".into(),
+            },
+        ),
+        ("gold", conductor(ConductorPreset::Gold, 0.2.into())),
+        ("copper", conductor(ConductorPreset::Copper, 0.4.into())),
+        (
+            "aluminium",
+            conductor(ConductorPreset::Aluminium, "patchy".into()),
+        ),
+        (
+            "frosted",
+            MaterialDescription::RoughDielectric {
+                refractive_index: 1.5,
+                roughness: 0.3.into(),
+            },
+        ),
+        (
+            "light",
+            MaterialDescription::DiffuseLight {
+                emit: [15.0, 15.0, 15.0].into(),
+            },
+        ),
+    ]);
+
+    let objects = vec![
+        sphere(Vec3::new(0.0, -500.0, 0.0), 500.0, "ground"),
+        sphere(Vec3::new(-3.3, 1.0, 0.0), 1.0, "gold"),
+        sphere(Vec3::new(-1.1, 1.0, 0.0), 1.0, "copper"),
+        sphere(Vec3::new(1.1, 1.0, 0.0), 1.0, "aluminium"),
+        sphere(Vec3::new(3.3, 1.0, 0.0), 1.0, "frosted"),
+        sphere(Vec3::new(-2.0, 6.0, 4.0), 1.0, "light"),
+    ];
+
+    // Camera setup
+    let from = Vec3::new(0.0, 3.0, 9.0);
+    let to = Vec3::new(0.0, 1.0, 0.0);
+    let aperture = 0.0;
+
+    SceneDescription {
+        camera: camera(from, to, 45.0, aperture),
+        background: Background::Color(Vec3::new(0.1, 0.1, 0.12)),
+        textures,
+        materials,
+        objects,
+        ..SceneDescription::default()
+    }
+}
+
+fn principled_scene() -> SceneDescription {
+    // Only the parameters used below, the rest are left at their defaults
+    fn principled(
+        base_color: [f32; 3],
+        metallic: TextureRef,
+        roughness: f32,
+        clearcoat: f32,
+        sheen: f32,
+        transmission: f32,
+    ) -> MaterialDescription {
+        MaterialDescription::Principled {
+            base_color: base_color.into(),
+            metallic,
+            roughness: roughness.into(),
+            specular: 0.5.into(),
+            clearcoat: clearcoat.into(),
+            sheen: sheen.into(),
+            transmission: transmission.into(),
+        }
+    }
+
+    let mut textures = BTreeMap::new();
+    textures.insert(
+        "ground".to_owned(),
+        TextureDescription::Checkered {
+            odd: [0.35, 0.35, 0.35].into(),
+            even: [0.6, 0.6, 0.6].into(),
+        },
+    );
+    // Patches of bare metal showing through paint
+    textures.insert(
+        "chipped".to_owned(),
+        TextureDescription::Checkered {
+            odd: 0.0.into(),
+            even: 1.0.into(),
+        },
+    );
+
+    let materials = materials(vec![
+        (
+            "ground",
+            MaterialDescription::Lambertian {
+                albedo: "ground".into(),
+            },
+        ),
+        (
+            "plastic",
+            principled([0.7, 0.1, 0.1], 0.0.into(), 0.3, 0.0, 0.0, 0.0),
+        ),
+        (
+            "brushed",
+            principled([1.0, 0.78, 0.34], 1.0.into(), 0.35, 0.0, 0.0, 0.0),
+        ),
+        (
+            "paint",
+            principled([0.05, 0.15, 0.6], "chipped".into(), 0.6, 1.0, 0.0, 0.0),
+        ),
+        (
+            "velvet",
+            principled([0.4, 0.05, 0.1], 0.0.into(), 1.0, 0.0, 1.0, 0.0),
+        ),
+        (
+            "glass",
+            principled([0.8, 0.95, 0.9], 0.0.into(), 0.1, 0.0, 0.0, 1.0),
+        ),
+        (
+            "light",
+            MaterialDescription::DiffuseLight {
+                emit: [15.0, 15.0, 15.0].into(),
+            },
+        ),
+    ]);
+
+    let objects = vec![
+        sphere(Vec3::new(0.0, -500.0, 0.0), 500.0, "ground"),
+        sphere(Vec3::new(-4.4, 1.0, 0.0), 1.0, "plastic"),
+        sphere(Vec3::new(-2.2, 1.0, 0.0), 1.0, "brushed"),
+        sphere(Vec3::new(0.0, 1.0, 0.0), 1.0, "paint"),
+        sphere(Vec3::new(2.2, 1.0, 0.0), 1.0, "velvet"),
+        sphere(Vec3::new(4.4, 1.0, 0.0), 1.0, "glass"),
+        sphere(Vec3::new(-2.0, 6.0, 4.0), 1.0, "light"),
+    ];
+
+    // Camera setup
+    let from = Vec3::new(0.0, 3.0, 11.0);
+    let to = Vec3::new(0.0, 1.0, 0.0);
+    let aperture = 0.0;
+
+    SceneDescription {
+        camera: camera(from, to, 45.0, aperture),
+        background: Background::Color(Vec3::new(0.1, 0.1, 0.12)),
+        textures,
+        materials,
+        objects,
+        ..SceneDescription::default()
+    }
+}
+
+fn glass_scene() -> SceneDescription {
+    fn glass(
+        absorption: Option<Absorption>,
+        surface_color: Option<TextureRef>,
+    ) -> MaterialDescription {
+        MaterialDescription::Dielectric {
+            refractive_index: 1.5,
+            absorption,
+            surface_color,
+            dispersion: None,
+        }
+    }
+
+    let mut textures = BTreeMap::new();
+    textures.insert(
+        "ground".to_owned(),
+        TextureDescription::Checkered {
+            odd: [0.35, 0.35, 0.35].into(),
+            even: [0.6, 0.6, 0.6].into(),
+        },
+    );
+    // Stained glass, clear with blue panes
+    textures.insert(
+        "stained".to_owned(),
+        TextureDescription::Checkered {
+            odd: [1.0, 1.0, 1.0].into(),
+            even: [0.3, 0.5, 0.95].into(),
+        },
+    );
+
+    let bottle = Absorption {
+        color: Vec3::new(0.3, 0.8, 0.4),
+        density: 1.0,
+    };
+    let amber = Absorption {
+        color: Vec3::new(0.95, 0.75, 0.35),
+        density: 1.5,
+    };
+    let materials = materials(vec![
+        (
+            "ground",
+            MaterialDescription::Lambertian {
+                albedo: "ground".into(),
+            },
+        ),
+        ("clear", glass(None, None)),
+        ("bottle", glass(Some(bottle), None)),
+        ("amber", glass(Some(amber), None)),
+        ("stained", glass(None, Some("stained".into()))),
+        (
+            "light",
+            MaterialDescription::DiffuseLight {
+                emit: [15.0, 15.0, 15.0].into(),
+            },
+        ),
+    ]);
+
+    let objects = vec![
+        sphere(Vec3::new(0.0, -500.0, 0.0), 500.0, "ground"),
+        sphere(Vec3::new(-3.3, 1.0, 0.0), 1.0, "clear"),
+        sphere(Vec3::new(-1.1, 1.0, 0.0), 1.0, "bottle"),
+        sphere(Vec3::new(1.1, 1.0, 0.0), 1.0, "amber"),
+        sphere(Vec3::new(3.3, 1.0, 0.0), 1.0, "stained"),
+        sphere(Vec3::new(-2.0, 6.0, 4.0), 1.0, "light"),
+    ];
+
+    // Camera setup
+    let from = Vec3::new(0.0, 3.0, 9.0);
+    let to = Vec3::new(0.0, 1.0, 0.0);
+    let aperture = 0.0;
+
+    SceneDescription {
+        camera: camera(from, to, 45.0, aperture),
+        background: Background::Color(Vec3::new(0.1, 0.1, 0.12)),
+        textures,
+        materials,
+        objects,
+        ..SceneDescription::default()
+    }
+}
+
+// Best rendered with --spectral, otherwise the glass doesn't split the light
+fn prism_scene() -> SceneDescription {
+    fn glass(refractive_index: f32, dispersion: Dispersion) -> MaterialDescription {
+        MaterialDescription::Dielectric {
+            refractive_index,
+            absorption: None,
+            surface_color: None,
+            dispersion: Some(dispersion),
+        }
+    }
+
+    let materials = materials(vec![
+        (
+            "ground",
+            MaterialDescription::Lambertian {
+                albedo: [0.5, 0.5, 0.5].into(),
+            },
+        ),
+        ("flint", glass(1.78, Dispersion::SF11)),
+        ("crown", glass(1.52, Dispersion::BK7)),
+        (
+            "light",
+            MaterialDescription::DiffuseLight {
+                emit: [4.0, 4.0, 4.0].into(),
+            },
+        ),
+        (
+            "strip",
+            MaterialDescription::DiffuseLight {
+                emit: [20.0, 20.0, 20.0].into(),
+            },
+        ),
+    ]);
+
+    // A prism lying along the x axis, hanging point down. Looking down
+    // into it bends the view back up onto the thin light behind, which comes
+    // out spread into a rainbow.
+    let (point, top) = (1.0, 1.0 + 3.0_f32.sqrt());
+    let corners = [(1.0, top), (-1.0, top), (0.0, point)];
+    let left = |(z, y): (f32, f32)| Vec3::new(-1.5, y, z);
+    let right = |(z, y): (f32, f32)| Vec3::new(1.5, y, z);
+    let triangle = |vertices: [Vec3; 3]| ObjectDescription::Triangle {
+        vertices,
+        normals: None,
+        uvs: None,
+        material: "flint".to_owned(),
+    };
+    let [a, b, c] = corners;
+    let mut objects = vec![
+        sphere(Vec3::new(0.0, -500.0, 0.0), 500.0, "ground"),
+        triangle([left(a), left(b), left(c)]),
+        triangle([right(a), right(c), right(b)]),
+    ];
+    // Each side as two triangles facing outwards
+    for i in 0..3 {
+        let (p, q) = (corners[i], corners[(i + 1) % 3]);
+        objects.push(triangle([left(p), right(q), left(q)]));
+        objects.push(triangle([left(p), right(p), right(q)]));
+    }
+    objects.extend(vec![
+        sphere(Vec3::new(3.0, 0.7, 1.0), 0.7, "crown"),
+        ObjectDescription::XYRect {
+            x0: -6.0,
+            x1: 6.0,
+            y0: 4.4,
+            y1: 4.6,
+            k: -5.0,
+            material: "strip".to_owned(),
+            flip: false,
+        },
+        sphere(Vec3::new(-4.0, 8.0, 2.0), 1.5, "light"),
+    ]);
+
+    // Camera setup
+    let from = Vec3::new(0.5, 7.5, 9.0);
+    let to = Vec3::new(0.5, 1.6, 0.0);
+    let aperture = 0.0;
+
+    SceneDescription {
+        camera: camera(from, to, 40.0, aperture),
+        background: Background::Color(Vec3::new(0.02, 0.02, 0.03)),
+        materials,
+        objects,
+        ..SceneDescription::default()
+    }
+}
@@ -0,0 +1,838 @@
+use serde::{Deserialize, Serialize};
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    bvh::Bvh,
+    camera::Camera,
+    color::ColorSpace,
+    material::{
+        Absorption, Checkered, Conductor, ConductorPreset, Dielectric, DiffuseLight, Dispersion,
+        Filter, Image, Isotropic, Lambertian, Material, Metal, Noise, Principled, RoughDielectric,
+        SolidColor, Texture, Wrap,
+    },
+    matrix::Mat4,
+    scene::{Background, Scene, SceneError},
+    shapes::{
+        ConstantMedium, Cuboid, FlipNormals, Hittable, MSphere, Mesh, RotateX, RotateY, RotateZ,
+        Sphere, Transform, Translate, Triangle, XYRect, XZRect, YZRect,
+    },
+    vector::Vec3,
+};
+
+// Everything needed to build a scene, in the form it is stored on disk.
+// Textures and materials are named so objects can share them.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SceneDescription {
+    pub camera: CameraDescription,
+    #[serde(default)]
+    pub background: Background,
+    #[serde(default)]
+    pub textures: BTreeMap<String, TextureDescription>,
+    #[serde(default)]
+    pub materials: BTreeMap<String, MaterialDescription>,
+    // Objects that aren't part of the scene themselves but can be placed any
+    // number of times with `Instance`, sharing the same geometry
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub prototypes: BTreeMap<String, ObjectDescription>,
+    #[serde(default)]
+    pub objects: Vec<ObjectDescription>,
+    // Directory relative paths in the scene are resolved against, set when
+    // the scene is loaded from a file
+    #[serde(skip)]
+    pub base_dir: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CameraDescription {
+    pub look_from: Vec3,
+    pub look_at: Vec3,
+    #[serde(default = "default_up")]
+    pub up: Vec3,
+    pub vfov: f32,
+    #[serde(default)]
+    pub aperture: f32,
+    // Defaults to the distance between `look_from` and `look_at`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub focus_dist: Option<f32>,
+    #[serde(default)]
+    pub time_0: f32,
+    #[serde(default)]
+    pub time_1: f32,
+}
+
+fn default_up() -> Vec3 {
+    Vec3::new(0.0, 1.0, 0.0)
+}
+
+impl Default for CameraDescription {
+    fn default() -> Self {
+        CameraDescription {
+            look_from: Vec3::new(0.0, 0.0, 0.0),
+            look_at: Vec3::new(0.0, 0.0, -1.0),
+            up: default_up(),
+            vfov: 90.0,
+            aperture: 0.0,
+            focus_dist: None,
+            time_0: 0.0,
+            time_1: 0.0,
+        }
+    }
+}
+
+// A texture is either written inline as a plain `[r, g, b]` colour or a
+// single number used for every channel, or refers to an entry in the scene's
+// `textures` by name
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum TextureRef {
+    Value(f32),
+    Color(Vec3),
+    Named(String),
+}
+
+impl From<[f32; 3]> for TextureRef {
+    fn from(color: [f32; 3]) -> Self {
+        TextureRef::Color(color.into())
+    }
+}
+
+impl From<f32> for TextureRef {
+    fn from(val: f32) -> Self {
+        TextureRef::Value(val)
+    }
+}
+
+impl From<&str> for TextureRef {
+    fn from(name: &str) -> Self {
+        TextureRef::Named(name.to_owned())
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum TextureDescription {
+    SolidColor {
+        color: Vec3,
+    },
+    Checkered {
+        odd: TextureRef,
+        even: TextureRef,
+    },
+    Noise {
+        scale: f32,
+    },
+    // Raw 8 bit RGB data, `width * height * 3` bytes long
+    Image {
+        width: usize,
+        height: usize,
+        data: Vec<u8>,
+        #[serde(flatten)]
+        options: ImageOptions,
+    },
+    // PPM, PNG or JPEG file, relative to the scene file
+    ImageFile {
+        path: PathBuf,
+        #[serde(flatten)]
+        options: ImageOptions,
+    },
+}
+
+// How an image texture is read and looked up, shared by both kinds of image
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ImageOptions {
+    // Set for data like roughness or normal maps that shouldn't be decoded
+    // from sRGB
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub non_color: bool,
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub filter: Filter,
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub wrap: Wrap,
+    #[serde(default = "default_uv_scale")]
+    pub scale: [f32; 2],
+    #[serde(default)]
+    pub offset: [f32; 2],
+}
+
+impl Default for ImageOptions {
+    fn default() -> Self {
+        ImageOptions {
+            non_color: false,
+            filter: Filter::default(),
+            wrap: Wrap::default(),
+            scale: default_uv_scale(),
+            offset: [0.0, 0.0],
+        }
+    }
+}
+
+impl ImageOptions {
+    fn color_space(&self) -> ColorSpace {
+        if self.non_color {
+            ColorSpace::Linear
+        } else {
+            ColorSpace::Srgb
+        }
+    }
+
+    fn apply(&self, image: Image) -> Image {
+        let [scale_u, scale_v] = self.scale;
+        let [offset_u, offset_v] = self.offset;
+        image
+            .with_filter(self.filter)
+            .with_wrap(self.wrap)
+            .with_transform((scale_u, scale_v), (offset_u, offset_v))
+    }
+}
+
+fn default_uv_scale() -> [f32; 2] {
+    [1.0, 1.0]
+}
+
+fn is_default<T: Default + PartialEq>(val: &T) -> bool {
+    *val == T::default()
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum MaterialDescription {
+    Lambertian {
+        albedo: TextureRef,
+    },
+    Metal {
+        albedo: TextureRef,
+        fuzz: f32,
+    },
+    Dielectric {
+        refractive_index: f32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        absorption: Option<Absorption>,
+        // Tints the light passing through the surface
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        surface_color: Option<TextureRef>,
+        // Only used when rendering spectrally, `refractive_index` is used
+        // otherwise
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        dispersion: Option<Dispersion>,
+    },
+    DiffuseLight {
+        emit: TextureRef,
+    },
+    // Phase function for a `ConstantMedium`
+    Isotropic {
+        albedo: TextureRef,
+    },
+    // Metal with a microfacet surface. Roughness goes from a perfect mirror
+    // at 0 to very rough at 1.
+    Conductor {
+        #[serde(flatten)]
+        ior: ConductorIor,
+        roughness: TextureRef,
+    },
+    // Glass with a microfacet surface
+    RoughDielectric {
+        refractive_index: f32,
+        roughness: TextureRef,
+    },
+    // One material that can be anything from plastic to metal to glass, see
+    // `Principled` for what the parameters do
+    Principled {
+        base_color: TextureRef,
+        #[serde(default = "default_zero")]
+        metallic: TextureRef,
+        #[serde(default = "default_half")]
+        roughness: TextureRef,
+        #[serde(default = "default_half")]
+        specular: TextureRef,
+        #[serde(default = "default_zero")]
+        clearcoat: TextureRef,
+        #[serde(default = "default_zero")]
+        sheen: TextureRef,
+        #[serde(default = "default_zero")]
+        transmission: TextureRef,
+    },
+}
+
+fn default_zero() -> TextureRef {
+    TextureRef::Value(0.0)
+}
+
+fn default_half() -> TextureRef {
+    TextureRef::Value(0.5)
+}
+
+// Either one of the built in metals or the real and imaginary parts of a
+// refractive index for each colour channel
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ConductorIor {
+    Preset { preset: ConductorPreset },
+    Custom { eta: Vec3, k: Vec3 },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ObjectDescription {
+    Sphere {
+        center: Vec3,
+        radius: f32,
+        material: String,
+    },
+    // Moving sphere. Like meshes these can't be aimed at if they give off
+    // light, so they'll only be found by rays that happen to hit them.
+    MSphere {
+        center_0: Vec3,
+        center_1: Vec3,
+        radius: f32,
+        time_0: f32,
+        time_1: f32,
+        material: String,
+    },
+    // Rectangles face the positive end of the axis they're perpendicular to
+    // unless `flip` is set
+    XYRect {
+        x0: f32,
+        x1: f32,
+        y0: f32,
+        y1: f32,
+        k: f32,
+        material: String,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        flip: bool,
+    },
+    XZRect {
+        x0: f32,
+        x1: f32,
+        z0: f32,
+        z1: f32,
+        k: f32,
+        material: String,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        flip: bool,
+    },
+    YZRect {
+        y0: f32,
+        y1: f32,
+        z0: f32,
+        z1: f32,
+        k: f32,
+        material: String,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        flip: bool,
+    },
+    Cuboid {
+        min: Vec3,
+        max: Vec3,
+        material: String,
+    },
+    // Vertices anticlockwise around the side the normal faces. Per vertex
+    // normals and texture coordinates are optional.
+    Triangle {
+        vertices: [Vec3; 3],
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        normals: Option<[Vec3; 3]>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        uvs: Option<[[f32; 2]; 3]>,
+        material: String,
+    },
+    // Wavefront OBJ file, `material` is used for faces the file doesn't give
+    // a material of its own. Meshes aren't sampled as lights, even if
+    // `material` gives off light.
+    Mesh {
+        path: PathBuf,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        material: Option<String>,
+    },
+    // Smoke or fog filling a convex `boundary`, `material` is the phase
+    // function and should usually be `Isotropic`
+    ConstantMedium {
+        boundary: Box<ObjectDescription>,
+        density: f32,
+        material: String,
+    },
+    Translate {
+        offset: Vec3,
+        object: Box<ObjectDescription>,
+    },
+    // Rotations are in degrees
+    RotateX {
+        angle: f32,
+        object: Box<ObjectDescription>,
+    },
+    RotateY {
+        angle: f32,
+        object: Box<ObjectDescription>,
+    },
+    RotateZ {
+        angle: f32,
+        object: Box<ObjectDescription>,
+    },
+    // An affine transform matrix given row by row
+    Transform {
+        matrix: Mat4,
+        object: Box<ObjectDescription>,
+    },
+    // Place one of the scene's `prototypes`
+    Instance {
+        name: String,
+    },
+}
+
+impl SceneDescription {
+    // Build the scene for an `x` by `y` image
+    pub fn build(&self, x: u64, y: u64) -> Result<Scene, SceneError> {
+        let cam = self.camera.build(x as f32 / y as f32);
+
+        let mut builder = Builder {
+            description: self,
+            textures: BTreeMap::new(),
+            materials: BTreeMap::new(),
+            prototypes: BTreeMap::new(),
+            building: Vec::new(),
+            warnings: Vec::new(),
+        };
+        let mut world = Vec::new();
+        let mut lights: Vec<Box<dyn Hittable>> = Vec::new();
+        for (index, object) in self.objects.iter().enumerate() {
+            let built = builder.object(object)?;
+            if self.is_light(object) {
+                // Shared so it can be in both lists
+                let light: Arc<dyn Hittable> = Arc::from(built);
+                lights.push(Box::new(Arc::clone(&light)));
+                world.push(Box::new(light) as Box<dyn Hittable>);
+            } else {
+                if self.gives_off_light(object) {
+                    builder.warnings.push(format!(
+                        "object {} gives off light but moving spheres and meshes can't be \
+                         sampled as lights, so it will be slow to converge",
+                        index
+                    ));
+                }
+                world.push(built);
+            }
+        }
+
+        let (time_0, time_1) = cam.time_interval();
+        Ok(Scene {
+            camera: cam,
+            world: Bvh::new(world, time_0, time_1),
+            lights,
+            background: self.background,
+            warnings: builder.warnings,
+        })
+    }
+
+    // Whether `object` gives off light and is a shape that can be sampled,
+    // either directly or moved into place
+    fn is_light(&self, object: &ObjectDescription) -> bool {
+        match object {
+            ObjectDescription::Sphere { material, .. }
+            | ObjectDescription::XYRect { material, .. }
+            | ObjectDescription::XZRect { material, .. }
+            | ObjectDescription::YZRect { material, .. }
+            | ObjectDescription::Cuboid { material, .. }
+            | ObjectDescription::Triangle { material, .. } => self.is_emissive(material),
+            ObjectDescription::Translate { object, .. }
+            | ObjectDescription::RotateX { object, .. }
+            | ObjectDescription::RotateY { object, .. }
+            | ObjectDescription::RotateZ { object, .. }
+            | ObjectDescription::Transform { object, .. } => self.is_light(object),
+            // Building the scene has already caught prototypes that contain
+            // themselves
+            ObjectDescription::Instance { name } => self
+                .prototypes
+                .get(name)
+                .is_some_and(|object| self.is_light(object)),
+            _ => false,
+        }
+    }
+
+    // Whether `object` gives off light at all, whether or not it can be
+    // sampled
+    fn gives_off_light(&self, object: &ObjectDescription) -> bool {
+        match object {
+            ObjectDescription::MSphere { material, .. } => self.is_emissive(material),
+            ObjectDescription::Mesh {
+                material: Some(material),
+                ..
+            } => self.is_emissive(material),
+            ObjectDescription::Translate { object, .. }
+            | ObjectDescription::RotateX { object, .. }
+            | ObjectDescription::RotateY { object, .. }
+            | ObjectDescription::RotateZ { object, .. }
+            | ObjectDescription::Transform { object, .. } => self.gives_off_light(object),
+            ObjectDescription::Instance { name } => self
+                .prototypes
+                .get(name)
+                .is_some_and(|object| self.gives_off_light(object)),
+            _ => self.is_light(object),
+        }
+    }
+
+    fn is_emissive(&self, material: &str) -> bool {
+        matches!(
+            self.materials.get(material),
+            Some(MaterialDescription::DiffuseLight { .. })
+        )
+    }
+}
+
+impl CameraDescription {
+    fn build(&self, aspect_ratio: f32) -> Camera {
+        let focus_dist = self
+            .focus_dist
+            .unwrap_or_else(|| (self.look_from - self.look_at).get_mag());
+        Camera::new(
+            self.look_from,
+            self.look_at,
+            self.up,
+            self.vfov,
+            aspect_ratio,
+            self.aperture,
+            focus_dist,
+            self.time_0,
+            self.time_1,
+        )
+    }
+}
+
+// Turns descriptions into scene objects, making sure each named texture and
+// material is only created once however many objects use it
+struct Builder<'a> {
+    description: &'a SceneDescription,
+    textures: BTreeMap<String, Arc<dyn Texture>>,
+    materials: BTreeMap<String, Arc<dyn Material>>,
+    prototypes: BTreeMap<String, Arc<dyn Hittable>>,
+    // Names of the textures and prototypes currently being built, used to
+    // catch ones that end up referring to themselves
+    building: Vec<String>,
+    warnings: Vec<String>,
+}
+
+impl<'a> Builder<'a> {
+    fn texture_ref(&mut self, texture: &TextureRef) -> Result<Arc<dyn Texture>, SceneError> {
+        match texture {
+            TextureRef::Value(val) => Ok(Arc::new(SolidColor::new(*val, *val, *val))),
+            TextureRef::Color(color) => Ok(Arc::new(SolidColor::new(color.x, color.y, color.z))),
+            TextureRef::Named(name) => self.named_texture(name),
+        }
+    }
+
+    fn named_texture(&mut self, name: &str) -> Result<Arc<dyn Texture>, SceneError> {
+        if let Some(texture) = self.textures.get(name) {
+            return Ok(Arc::clone(texture));
+        }
+        let description = self
+            .description
+            .textures
+            .get(name)
+            .ok_or_else(|| SceneError::MissingTexture(name.to_owned()))?;
+
+        let key = format!("texture {}", name);
+        if self.building.contains(&key) {
+            return Err(SceneError::Invalid(format!(
+                "texture `{}` refers to itself",
+                name
+            )));
+        }
+        self.building.push(key);
+
+        let texture: Arc<dyn Texture> = match description {
+            TextureDescription::SolidColor { color } => {
+                Arc::new(SolidColor::new(color.x, color.y, color.z))
+            }
+            TextureDescription::Checkered { odd, even } => Arc::new(Checkered::new(
+                self.texture_ref(odd)?,
+                self.texture_ref(even)?,
+            )),
+            TextureDescription::Noise { scale } => Arc::new(Noise::new(*scale)),
+            TextureDescription::Image {
+                width,
+                height,
+                data,
+                options,
+            } => {
+                if *width == 0 || *height == 0 {
+                    return Err(SceneError::Invalid(format!(
+                        "image texture `{}` has no pixels",
+                        name
+                    )));
+                }
+                let len = width
+                    .checked_mul(*height)
+                    .and_then(|pixels| pixels.checked_mul(3))
+                    .ok_or_else(|| {
+                        SceneError::Invalid(format!("image texture `{}` is too large", name))
+                    })?;
+                if data.len() != len {
+                    return Err(SceneError::Invalid(format!(
+                        "image texture `{}` should have {} bytes of data but has {}",
+                        name,
+                        len,
+                        data.len()
+                    )));
+                }
+                let image = Image::new(data.clone(), *width, *height, options.color_space());
+                Arc::new(options.apply(image))
+            }
+            TextureDescription::ImageFile { path, options } => {
+                let image = Image::load(&self.path(path), options.color_space())?;
+                Arc::new(options.apply(image))
+            }
+        };
+
+        self.building.pop();
+        self.textures.insert(name.to_owned(), Arc::clone(&texture));
+        Ok(texture)
+    }
+
+    fn material(&mut self, name: &str) -> Result<Arc<dyn Material>, SceneError> {
+        if let Some(material) = self.materials.get(name) {
+            return Ok(Arc::clone(material));
+        }
+        let description = self
+            .description
+            .materials
+            .get(name)
+            .ok_or_else(|| SceneError::MissingMaterial(name.to_owned()))?;
+
+        let material: Arc<dyn Material> = match description {
+            MaterialDescription::Lambertian { albedo } => {
+                Arc::new(Lambertian::new(self.texture_ref(albedo)?))
+            }
+            MaterialDescription::Metal { albedo, fuzz } => {
+                Arc::new(Metal::new(self.texture_ref(albedo)?, *fuzz))
+            }
+            MaterialDescription::Dielectric {
+                refractive_index,
+                absorption,
+                surface_color,
+                dispersion,
+            } => {
+                let mut dielectric = Dielectric::new(*refractive_index);
+                if let Some(absorption) = absorption {
+                    dielectric = dielectric.with_absorption(*absorption);
+                }
+                if let Some(surface_color) = surface_color {
+                    dielectric = dielectric.with_surface_color(self.texture_ref(surface_color)?);
+                }
+                if let Some(dispersion) = dispersion {
+                    dielectric = dielectric.with_dispersion(*dispersion);
+                }
+                Arc::new(dielectric)
+            }
+            MaterialDescription::DiffuseLight { emit } => {
+                Arc::new(DiffuseLight::new(self.texture_ref(emit)?))
+            }
+            MaterialDescription::Isotropic { albedo } => {
+                Arc::new(Isotropic::new(self.texture_ref(albedo)?))
+            }
+            MaterialDescription::Conductor { ior, roughness } => {
+                let (eta, k) = match ior {
+                    ConductorIor::Preset { preset } => preset.ior(),
+                    ConductorIor::Custom { eta, k } => (*eta, *k),
+                };
+                Arc::new(Conductor::new(eta, k, self.texture_ref(roughness)?))
+            }
+            MaterialDescription::RoughDielectric {
+                refractive_index,
+                roughness,
+            } => Arc::new(RoughDielectric::new(
+                *refractive_index,
+                self.texture_ref(roughness)?,
+            )),
+            MaterialDescription::Principled {
+                base_color,
+                metallic,
+                roughness,
+                specular,
+                clearcoat,
+                sheen,
+                transmission,
+            } => Arc::new(
+                Principled::new(self.texture_ref(base_color)?)
+                    .with_metallic(self.texture_ref(metallic)?)
+                    .with_roughness(self.texture_ref(roughness)?)
+                    .with_specular(self.texture_ref(specular)?)
+                    .with_clearcoat(self.texture_ref(clearcoat)?)
+                    .with_sheen(self.texture_ref(sheen)?)
+                    .with_transmission(self.texture_ref(transmission)?),
+            ),
+        };
+
+        self.materials
+            .insert(name.to_owned(), Arc::clone(&material));
+        Ok(material)
+    }
+
+    fn object(&mut self, object: &ObjectDescription) -> Result<Box<dyn Hittable>, SceneError> {
+        let object: Box<dyn Hittable> = match object {
+            ObjectDescription::Sphere {
+                center,
+                radius,
+                material,
+            } => Box::new(Sphere::new(*center, *radius, self.material(material)?)),
+            ObjectDescription::MSphere {
+                center_0,
+                center_1,
+                radius,
+                time_0,
+                time_1,
+                material,
+            } => Box::new(MSphere::new(
+                *center_0,
+                *center_1,
+                *radius,
+                *time_0,
+                *time_1,
+                self.material(material)?,
+            )),
+            ObjectDescription::XYRect {
+                x0,
+                x1,
+                y0,
+                y1,
+                k,
+                material,
+                flip,
+            } => {
+                let rect = XYRect::new(*x0, *x1, *y0, *y1, *k, self.material(material)?);
+                flip_if(Box::new(rect), *flip)
+            }
+            ObjectDescription::XZRect {
+                x0,
+                x1,
+                z0,
+                z1,
+                k,
+                material,
+                flip,
+            } => {
+                let rect = XZRect::new(*x0, *x1, *z0, *z1, *k, self.material(material)?);
+                flip_if(Box::new(rect), *flip)
+            }
+            ObjectDescription::YZRect {
+                y0,
+                y1,
+                z0,
+                z1,
+                k,
+                material,
+                flip,
+            } => {
+                let rect = YZRect::new(*y0, *y1, *z0, *z1, *k, self.material(material)?);
+                flip_if(Box::new(rect), *flip)
+            }
+            ObjectDescription::Cuboid { min, max, material } => {
+                Box::new(Cuboid::new(*min, *max, self.material(material)?))
+            }
+            ObjectDescription::Triangle {
+                vertices,
+                normals,
+                uvs,
+                material,
+            } => {
+                let mut tri = Triangle::new(*vertices, self.material(material)?);
+                if let Some(normals) = normals {
+                    tri = tri.with_normals(*normals);
+                }
+                if let Some([uv0, uv1, uv2]) = uvs {
+                    tri = tri.with_uvs([(uv0[0], uv0[1]), (uv1[0], uv1[1]), (uv2[0], uv2[1])]);
+                }
+                Box::new(tri)
+            }
+            ObjectDescription::Mesh { path, material } => {
+                let default_mat = match material {
+                    Some(material) => self.material(material)?,
+                    None => Arc::new(Lambertian::default()),
+                };
+                let mut mesh = Mesh::load_obj(&self.path(path), default_mat)?;
+                self.warnings.append(&mut mesh.warnings);
+                Box::new(mesh)
+            }
+            ObjectDescription::ConstantMedium {
+                boundary,
+                density,
+                material,
+            } => {
+                // Anything else gives infinite or NaN free paths
+                if !(density.is_finite() && *density > 0.0) {
+                    return Err(SceneError::Invalid(format!(
+                        "constant medium density must be above zero, not {}",
+                        density
+                    )));
+                }
+                Box::new(ConstantMedium::new(
+                    self.object(boundary)?,
+                    *density,
+                    self.material(material)?,
+                ))
+            }
+            ObjectDescription::Translate { offset, object } => {
+                Box::new(Translate::new(self.object(object)?, *offset))
+            }
+            ObjectDescription::RotateX { angle, object } => {
+                Box::new(RotateX::new(self.object(object)?, *angle))
+            }
+            ObjectDescription::RotateY { angle, object } => {
+                Box::new(RotateY::new(self.object(object)?, *angle))
+            }
+            ObjectDescription::RotateZ { angle, object } => {
+                Box::new(RotateZ::new(self.object(object)?, *angle))
+            }
+            ObjectDescription::Transform { matrix, object } => {
+                let transform = Transform::new(self.object(object)?, *matrix);
+                Box::new(transform.ok_or_else(|| {
+                    SceneError::Invalid("transform matrix can't be inverted".to_owned())
+                })?)
+            }
+            ObjectDescription::Instance { name } => Box::new(self.prototype(name)?),
+        };
+        Ok(object)
+    }
+
+    fn path(&self, path: &Path) -> PathBuf {
+        match &self.description.base_dir {
+            Some(dir) => dir.join(path),
+            None => path.to_owned(),
+        }
+    }
+
+    fn prototype(&mut self, name: &str) -> Result<Arc<dyn Hittable>, SceneError> {
+        if let Some(object) = self.prototypes.get(name) {
+            return Ok(Arc::clone(object));
+        }
+        let description = self
+            .description
+            .prototypes
+            .get(name)
+            .ok_or_else(|| SceneError::MissingObject(name.to_owned()))?;
+
+        let key = format!("object {}", name);
+        if self.building.contains(&key) {
+            return Err(SceneError::Invalid(format!(
+                "prototype `{}` contains itself",
+                name
+            )));
+        }
+        self.building.push(key);
+        let object: Arc<dyn Hittable> = Arc::from(self.object(description)?);
+        self.building.pop();
+
+        self.prototypes.insert(name.to_owned(), Arc::clone(&object));
+        Ok(object)
+    }
+}
+
+fn flip_if(object: Box<dyn Hittable>, flip: bool) -> Box<dyn Hittable> {
+    if flip {
+        Box::new(FlipNormals::new(object))
+    } else {
+        object
+    }
+}
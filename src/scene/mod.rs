@@ -0,0 +1,272 @@
+mod builtin;
+mod description;
+
+pub use description::SceneDescription;
+
+use serde::{Deserialize, Serialize};
+
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufReader, BufWriter, Write},
+    path::Path,
+};
+
+use crate::{
+    bvh::Bvh,
+    camera::Camera,
+    material::ImageError,
+    ray::Ray,
+    shapes::{Hittable, ObjError},
+    vector::Vec3,
+};
+
+pub struct Scene {
+    pub camera: Camera,
+    pub world: Bvh,
+    // Emitters that rays can be aimed at directly, these are also in `world`
+    pub lights: Vec<Box<dyn Hittable>>,
+    pub background: Background,
+    // Problems found while building the scene that don't stop it rendering
+    pub warnings: Vec<String>,
+}
+
+// What a ray sees when it doesn't hit anything
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Background {
+    // Gradient from white at the horizon to light blue overhead
+    #[default]
+    Sky,
+    Color(Vec3),
+}
+
+impl Background {
+    pub fn color(&self, ray: Ray) -> Vec3 {
+        match self {
+            Background::Sky => {
+                let unit_dir = ray.dir.get_unit();
+                // Interpolate along y axis
+                let t = (unit_dir.y + 1.0) * 0.5;
+                ((1.0 - t) * Vec3::new(1.0, 1.0, 1.0)) + (t * Vec3::new(0.5, 0.7, 1.0))
+            }
+            Background::Color(color) => *color,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(io::Error),
+    Parse(serde_json::Error),
+    UnknownScene(String),
+    MissingTexture(String),
+    MissingMaterial(String),
+    MissingObject(String),
+    Obj(ObjError),
+    Image(ImageError),
+    Invalid(String),
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SceneError::Io(err) => write!(f, "{}", err),
+            SceneError::Parse(err) => write!(f, "invalid scene file: {}", err),
+            SceneError::UnknownScene(name) => write!(
+                f,
+                "`{}` is not a built-in scene ({}) or a scene file",
+                name,
+                builtin::NAMES.join(", ")
+            ),
+            SceneError::MissingTexture(name) => write!(f, "no texture named `{}`", name),
+            SceneError::MissingMaterial(name) => write!(f, "no material named `{}`", name),
+            SceneError::MissingObject(name) => write!(f, "no prototype object named `{}`", name),
+            SceneError::Obj(err) => write!(f, "could not load mesh: {}", err),
+            SceneError::Image(err) => write!(f, "could not load image: {}", err),
+            SceneError::Invalid(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl From<io::Error> for SceneError {
+    fn from(err: io::Error) -> Self {
+        SceneError::Io(err)
+    }
+}
+
+impl From<ObjError> for SceneError {
+    fn from(err: ObjError) -> Self {
+        SceneError::Obj(err)
+    }
+}
+
+impl From<ImageError> for SceneError {
+    fn from(err: ImageError) -> Self {
+        SceneError::Image(err)
+    }
+}
+
+impl From<serde_json::Error> for SceneError {
+    fn from(err: serde_json::Error) -> Self {
+        SceneError::Parse(err)
+    }
+}
+
+// Load one of the built-in scenes by name, or otherwise treat `scene` as the
+// path to a scene file
+pub fn load_scene(scene: &str) -> Result<SceneDescription, SceneError> {
+    if let Some(description) = builtin::scene(scene) {
+        return Ok(description);
+    }
+
+    let path = Path::new(scene);
+    if !path.is_file() {
+        return Err(SceneError::UnknownScene(scene.to_owned()));
+    }
+    let reader = BufReader::new(File::open(path)?);
+    let mut description: SceneDescription = serde_json::from_reader(reader)?;
+    // Files the scene refers to are found relative to the scene itself
+    description.base_dir = path.parent().map(Path::to_owned);
+    Ok(description)
+}
+
+pub fn export_scene(description: &SceneDescription, path: &Path) -> Result<(), SceneError> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(&mut writer, description)?;
+    writer.write_all(b"\n")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scene::{builtin, SceneDescription, SceneError};
+
+    #[test]
+    fn builtin_scenes_round_trip() {
+        for name in builtin::NAMES.iter() {
+            let description = builtin::scene(name).unwrap();
+            let json = serde_json::to_string(&description).unwrap();
+            let parsed: SceneDescription = serde_json::from_str(&json).unwrap();
+
+            assert_eq!(description.objects.len(), parsed.objects.len());
+            assert!(
+                parsed.build(20, 10).is_ok(),
+                "scene `{}` failed to build",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn missing_material() {
+        let json = r#"{
+            "camera": { "look_from": [0, 0, 0], "look_at": [0, 0, -1], "vfov": 60 },
+            "objects": [
+                { "type": "Sphere", "center": [0, 0, -1], "radius": 0.5, "material": "gold" }
+            ]
+        }"#;
+        let description: SceneDescription = serde_json::from_str(json).unwrap();
+        match description.build(20, 10) {
+            Err(SceneError::MissingMaterial(name)) => assert_eq!(name, "gold"),
+            _ => panic!("expected a missing material error"),
+        }
+    }
+
+    #[test]
+    fn light_shapes() {
+        let json = r#"{
+            "camera": { "look_from": [0, 0, 0], "look_at": [0, 0, -1], "vfov": 60 },
+            "materials": {
+                "light": { "type": "DiffuseLight", "emit": 1 },
+                "matte": { "type": "Lambertian", "albedo": 0.5 }
+            },
+            "prototypes": {
+                "lamp": { "type": "Cuboid", "min": [0, 0, 0], "max": [1, 1, 1], "material": "light" }
+            },
+            "objects": [
+                { "type": "Cuboid", "min": [0, 0, 0], "max": [1, 1, 1], "material": "light" },
+                {
+                    "type": "Triangle", "vertices": [[0, 0, 0], [1, 0, 0], [0, 1, 0]],
+                    "material": "light"
+                },
+                {
+                    "type": "Transform",
+                    "matrix": [[2, 0, 0, 0], [0, 1, 0, 0], [0, 0, 1, 0], [0, 0, 0, 1]],
+                    "object": { "type": "Instance", "name": "lamp" }
+                },
+                { "type": "Sphere", "center": [0, 0, -1], "radius": 0.5, "material": "matte" },
+                {
+                    "type": "MSphere", "center_0": [0, 0, -1], "center_1": [0, 1, -1],
+                    "radius": 0.5, "time_0": 0, "time_1": 1, "material": "light"
+                }
+            ]
+        }"#;
+        let description: SceneDescription = serde_json::from_str(json).unwrap();
+        let scene = description.build(20, 10).unwrap();
+        assert_eq!(scene.lights.len(), 3);
+        // The moving sphere can't be sampled so it gets a warning instead
+        assert_eq!(scene.warnings.len(), 1);
+        assert!(scene.warnings[0].contains("object 4"));
+    }
+
+    #[test]
+    fn medium_density() {
+        for density in ["0", "-1", "1e40"] {
+            let json = format!(
+                r#"{{
+                    "camera": {{ "look_from": [0, 0, 0], "look_at": [0, 0, -1], "vfov": 60 }},
+                    "materials": {{ "fog": {{ "type": "Isotropic", "albedo": 1 }} }},
+                    "objects": [
+                        {{
+                            "type": "ConstantMedium", "density": {}, "material": "fog",
+                            "boundary": {{
+                                "type": "Sphere", "center": [0, 0, -1], "radius": 0.5,
+                                "material": "fog"
+                            }}
+                        }}
+                    ]
+                }}"#,
+                density
+            );
+            let description: SceneDescription = serde_json::from_str(&json).unwrap();
+            match description.build(20, 10) {
+                Err(SceneError::Invalid(msg)) => assert!(msg.contains("density")),
+                _ => panic!("expected a density of {} to be rejected", density),
+            }
+        }
+    }
+
+    #[test]
+    fn image_sizes() {
+        let json = r#"{
+            "camera": { "look_from": [0, 0, 0], "look_at": [0, 0, -1], "vfov": 60 },
+            "textures": {
+                "empty": { "type": "Image", "width": 0, "height": 4, "data": [] }
+            },
+            "materials": {
+                "matte": { "type": "Lambertian", "albedo": "empty" }
+            },
+            "objects": [
+                { "type": "Sphere", "center": [0, 0, -1], "radius": 0.5, "material": "matte" }
+            ]
+        }"#;
+        let description: SceneDescription = serde_json::from_str(json).unwrap();
+        match description.build(20, 10) {
+            Err(SceneError::Invalid(msg)) => assert!(msg.contains("no pixels")),
+            _ => panic!("expected an empty image to be rejected"),
+        }
+
+        // Sizes whose product doesn't fit are an error rather than an
+        // overflow
+        let huge = json.replace(
+            r#""width": 0, "height": 4"#,
+            &format!(r#""width": {}, "height": 4"#, usize::MAX / 2),
+        );
+        let description: SceneDescription = serde_json::from_str(&huge).unwrap();
+        match description.build(20, 10) {
+            Err(SceneError::Invalid(msg)) => assert!(msg.contains("too large")),
+            _ => panic!("expected a huge image to be rejected"),
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use std::sync::Arc;
+
+use crate::{
+    aabb::Aabb,
+    material::Material,
+    ray::{Ray, RayHit},
+    shapes::Hittable,
+    utils::gen_random,
+    vector::Vec3,
+};
+
+// A volume of smoke or fog filling `boundary` with the same density
+// everywhere. Rays travel a random distance through it before scattering off
+// the phase function, which is usually `Isotropic`. The boundary has to be
+// convex since only the first entry and exit points are found.
+pub struct ConstantMedium {
+    boundary: Box<dyn Hittable>,
+    density: f32,
+    phase_function: Arc<dyn Material>,
+}
+
+impl ConstantMedium {
+    pub fn new(
+        boundary: Box<dyn Hittable>,
+        density: f32,
+        phase_function: Arc<dyn Material>,
+    ) -> Self {
+        ConstantMedium {
+            boundary,
+            density,
+            phase_function,
+        }
+    }
+}
+
+impl Hittable for ConstantMedium {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<RayHit> {
+        // Find where the ray enters and leaves the boundary, even if the
+        // origin is already inside it. Moving boundaries use the ray's time
+        // like any other object so the volume is motion blurred too.
+        let enter = self.boundary.hit(ray, f32::MIN, f32::MAX)?;
+        let exit = self.boundary.hit(ray, enter.t + 0.0001, f32::MAX)?;
+
+        let t_enter = enter.t.max(t_min).max(0.0);
+        let t_exit = exit.t.min(t_max);
+        if t_enter >= t_exit {
+            return None;
+        }
+
+        // Sample the exponential free path, `1 - gen_random()` is never zero.
+        // This can't come from the pixel's sampler like other random choices
+        // since `hit` doesn't get one, and the medium is tested a varying
+        // number of times per path (once for each ray the BVH sends its way,
+        // shadow rays included) so it wouldn't keep to a fixed dimension.
+        let length = ray.dir.get_mag();
+        let inside = (t_exit - t_enter) * length;
+        let hit_distance = -(1.0 - gen_random()).ln() / self.density;
+        if hit_distance > inside {
+            return None;
+        }
+
+        let t = t_enter + hit_distance / length;
+        // The normal doesn't mean anything inside a volume
+        Some(RayHit::new(
+            t,
+            0.0,
+            0.0,
+            ray.point_at_parameter(t),
+            Vec3::new(1.0, 0.0, 0.0),
+            Arc::clone(&self.phase_function),
+        ))
+    }
+
+    fn bounding_box(&self, time_0: f32, time_1: f32) -> Option<Aabb> {
+        self.boundary.bounding_box(time_0, time_1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        material::{Isotropic, SolidColor},
+        ray::Ray,
+        shapes::{ConstantMedium, Hittable, Sphere},
+        vector::Vec3,
+    };
+
+    fn medium(density: f32) -> ConstantMedium {
+        let phase = Arc::new(Isotropic::new(Arc::new(SolidColor::new(1.0, 1.0, 1.0))));
+        let boundary = Sphere::new(Vec3::new(0.0, 0.0, 0.0), 1.0, phase.clone());
+        ConstantMedium::new(Box::new(boundary), density, phase)
+    }
+
+    #[test]
+    fn free_path() {
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -2.0), 0.0);
+
+        // Very dense media scatter right at the boundary
+        let hit = medium(1e6).hit(&ray, 0.001, f32::MAX).unwrap();
+        assert!((hit.point.z - 1.0).abs() < 1e-3);
+
+        // Very thin ones almost never scatter
+        let hits = (0..100)
+            .filter(|_| medium(1e-6).hit(&ray, 0.001, f32::MAX).is_some())
+            .count();
+        assert!(hits < 5);
+
+        // Starting inside the volume still works
+        let inside = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        let hit = medium(1e6).hit(&inside, 0.001, f32::MAX).unwrap();
+        assert!(hit.point.x < 0.01);
+
+        let miss = Ray::new(Vec3::new(0.0, 2.0, 5.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        assert!(medium(1e6).hit(&miss, 0.001, f32::MAX).is_none());
+    }
+}
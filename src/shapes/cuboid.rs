@@ -0,0 +1,130 @@
+use std::sync::Arc;
+
+use crate::{
+    aabb::Aabb,
+    material::Material,
+    ray::{Ray, RayHit},
+    sampler::Sampler,
+    shapes::{FlipNormals, Hittable, XYRect, XZRect, YZRect},
+    vector::Vec3,
+};
+
+// An axis aligned box made from six rectangles, all facing outwards
+pub struct Cuboid {
+    min: Vec3,
+    max: Vec3,
+    sides: Vec<Box<dyn Hittable>>,
+}
+
+impl Cuboid {
+    pub fn new(p0: Vec3, p1: Vec3, mat: Arc<dyn Material>) -> Self {
+        let min = Vec3::new(p0.x.min(p1.x), p0.y.min(p1.y), p0.z.min(p1.z));
+        let max = Vec3::new(p0.x.max(p1.x), p0.y.max(p1.y), p0.z.max(p1.z));
+
+        let sides: Vec<Box<dyn Hittable>> = vec![
+            Box::new(XYRect::new(
+                min.x,
+                max.x,
+                min.y,
+                max.y,
+                max.z,
+                Arc::clone(&mat),
+            )),
+            Box::new(FlipNormals::new(Box::new(XYRect::new(
+                min.x,
+                max.x,
+                min.y,
+                max.y,
+                min.z,
+                Arc::clone(&mat),
+            )))),
+            Box::new(XZRect::new(
+                min.x,
+                max.x,
+                min.z,
+                max.z,
+                max.y,
+                Arc::clone(&mat),
+            )),
+            Box::new(FlipNormals::new(Box::new(XZRect::new(
+                min.x,
+                max.x,
+                min.z,
+                max.z,
+                min.y,
+                Arc::clone(&mat),
+            )))),
+            Box::new(YZRect::new(
+                min.y,
+                max.y,
+                min.z,
+                max.z,
+                max.x,
+                Arc::clone(&mat),
+            )),
+            Box::new(FlipNormals::new(Box::new(YZRect::new(
+                min.y, max.y, min.z, max.z, min.x, mat,
+            )))),
+        ];
+
+        Cuboid { min, max, sides }
+    }
+}
+
+impl Hittable for Cuboid {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<RayHit> {
+        self.sides.hit(ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self, _time_0: f32, _time_1: f32) -> Option<Aabb> {
+        Some(Aabb::new(self.min, self.max))
+    }
+
+    fn pdf_value(&self, ray: &Ray) -> f32 {
+        self.sides.pdf_value(ray)
+    }
+
+    fn random(&self, origin: Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        self.sides.random(origin, sampler)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        material::Lambertian,
+        ray::Ray,
+        shapes::{Cuboid, Hittable},
+        vector::{dot, Vec3},
+    };
+
+    #[test]
+    fn face_normals() {
+        // Corners given in the wrong order on purpose, the box should sort them
+        let mat = Arc::new(Lambertian::default());
+        let cuboid = Cuboid::new(Vec3::new(1.0, 1.0, 1.0), Vec3::new(-1.0, -1.0, -1.0), mat);
+        let center = Vec3::new(0.1, -0.2, 0.15);
+
+        let axes = [
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+        ];
+        for &axis in &axes {
+            for &dir in &[axis, -axis] {
+                // Shoot at each face from outside, every normal should point
+                // back out towards the ray
+                let origin = center + dir * 5.0;
+                let hit = cuboid.hit(&Ray::new(origin, -dir, 0.0), 0.001, f32::MAX);
+                let hit = hit.expect("ray at a face missed");
+                assert!((hit.t - 4.0 - dot(&center, &dir)).abs() < 1e-5);
+                assert!((hit.normal - dir).get_mag() < 1e-6);
+            }
+        }
+
+        let ray = Ray::new(Vec3::new(3.0, 3.0, 5.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        assert!(cuboid.hit(&ray, 0.001, f32::MAX).is_none());
+    }
+}
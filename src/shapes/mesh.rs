@@ -0,0 +1,483 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::{Path, PathBuf},
+    str::{FromStr, SplitWhitespace},
+    sync::Arc,
+};
+
+use crate::{
+    aabb::Aabb,
+    bvh::Bvh,
+    color::ColorSpace,
+    material::{Dielectric, Filter, Image, Lambertian, Material, Metal, SolidColor, Texture, Wrap},
+    ray::{Ray, RayHit},
+    shapes::{Hittable, Triangle},
+    vector::Vec3,
+};
+
+// A triangle mesh with its own bounding volume hierarchy, so large meshes
+// only cost a few box tests per ray
+pub struct Mesh {
+    triangles: Bvh,
+    // Problems found loading the mesh that didn't stop it loading, for the
+    // caller to pass on
+    pub warnings: Vec<String>,
+}
+
+impl Mesh {
+    pub fn new(triangles: Vec<Triangle>) -> Self {
+        let triangles = triangles
+            .into_iter()
+            .map(|tri| Box::new(tri) as Box<dyn Hittable>)
+            .collect();
+        // Triangles don't move so the time interval doesn't matter
+        Mesh {
+            triangles: Bvh::new(triangles, 0.0, 0.0),
+            warnings: Vec::new(),
+        }
+    }
+
+    // Load a Wavefront OBJ file. Faces use the materials from the file's
+    // `mtllib`, or `default_mat` if they don't have one or it can't be found.
+    pub fn load_obj(path: &Path, default_mat: Arc<dyn Material>) -> Result<Self, ObjError> {
+        let file = File::open(path).map_err(|err| ObjError::Io(path.to_owned(), err))?;
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+        let mut positions: Vec<Vec3> = Vec::new();
+        let mut texcoords: Vec<(f32, f32)> = Vec::new();
+        let mut normals: Vec<Vec3> = Vec::new();
+        let mut materials: HashMap<String, Arc<dyn Material>> = HashMap::new();
+        let mut mat = Arc::clone(&default_mat);
+        // Unknown material names, each only warned about once
+        let mut missing = HashSet::new();
+        let mut warnings = Vec::new();
+        let mut triangles = Vec::new();
+
+        for (index, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.map_err(|err| ObjError::Io(path.to_owned(), err))?;
+            let mut parser = LineParser::new(path, index + 1, &line);
+
+            match parser.keyword() {
+                Some("v") => positions.push(parser.vec3()?),
+                Some("vt") => {
+                    let u = parser.value()?;
+                    // `v` is optional for one dimensional textures
+                    let v = parser.optional_value()?.unwrap_or(0.0);
+                    texcoords.push((u, v));
+                }
+                Some("vn") => normals.push(parser.vec3()?),
+                Some("f") => {
+                    let mut corners = Vec::new();
+                    while let Some(corner) =
+                        parser.face_corner(positions.len(), texcoords.len(), normals.len())?
+                    {
+                        corners.push(corner);
+                    }
+                    if corners.len() < 3 {
+                        return Err(parser.error("face needs at least three vertices"));
+                    }
+
+                    // Split polygons into a fan of triangles
+                    for i in 1..corners.len() - 1 {
+                        let face = [corners[0], corners[i], corners[i + 1]];
+                        let vertices = [
+                            positions[face[0].0],
+                            positions[face[1].0],
+                            positions[face[2].0],
+                        ];
+                        let mut tri = Triangle::new(vertices, Arc::clone(&mat));
+                        if let [Some(t0), Some(t1), Some(t2)] = [face[0].1, face[1].1, face[2].1] {
+                            tri = tri.with_uvs([texcoords[t0], texcoords[t1], texcoords[t2]]);
+                        }
+                        if let [Some(n0), Some(n1), Some(n2)] = [face[0].2, face[1].2, face[2].2] {
+                            tri = tri.with_normals([normals[n0], normals[n1], normals[n2]]);
+                        }
+                        triangles.push(tri);
+                    }
+                }
+                Some("mtllib") => {
+                    for name in parser.rest() {
+                        materials.extend(load_mtl(&dir.join(name))?);
+                    }
+                }
+                Some("usemtl") => {
+                    let name = parser.name()?;
+                    mat = match materials.get(name) {
+                        Some(mat) => Arc::clone(mat),
+                        None => {
+                            if missing.insert(name.to_owned()) {
+                                warnings.push(format!(
+                                    "{}:{}: unknown material `{}`, using the default instead",
+                                    path.display(),
+                                    index + 1,
+                                    name
+                                ));
+                            }
+                            Arc::clone(&default_mat)
+                        }
+                    };
+                }
+                // Groups, smoothing groups, lines, points etc. don't matter here
+                _ => {}
+            }
+        }
+
+        if triangles.is_empty() {
+            return Err(ObjError::Parse {
+                path: path.to_owned(),
+                line: 0,
+                msg: "file contains no faces".to_owned(),
+            });
+        }
+
+        let mut mesh = Mesh::new(triangles);
+        mesh.warnings = warnings;
+        Ok(mesh)
+    }
+}
+
+impl Hittable for Mesh {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<RayHit> {
+        self.triangles.hit(ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self, time_0: f32, time_1: f32) -> Option<Aabb> {
+        self.triangles.bounding_box(time_0, time_1)
+    }
+}
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io(PathBuf, io::Error),
+    Parse {
+        path: PathBuf,
+        line: usize,
+        msg: String,
+    },
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ObjError::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+            ObjError::Parse { path, line, msg } => {
+                write!(f, "{}:{}: {}", path.display(), line, msg)
+            }
+        }
+    }
+}
+
+// The parts of an MTL material we can map onto our own materials
+struct MtlMaterial {
+    diffuse: Vec3,
+    diffuse_map: Option<Arc<dyn Texture>>,
+    specular: Vec3,
+    shininess: f32,
+    refractive_index: f32,
+    dissolve: f32,
+    illum: u32,
+}
+
+impl Default for MtlMaterial {
+    fn default() -> Self {
+        MtlMaterial {
+            diffuse: Vec3::new(0.8, 0.8, 0.8),
+            diffuse_map: None,
+            specular: Vec3::new(0.0, 0.0, 0.0),
+            shininess: 0.0,
+            refractive_index: 1.5,
+            dissolve: 1.0,
+            illum: 2,
+        }
+    }
+}
+
+impl MtlMaterial {
+    fn to_material(&self) -> Arc<dyn Material> {
+        // Illumination models 4, 6, 7 and 9 are the glass ones
+        let transparent = self.dissolve < 1.0 || [4, 6, 7, 9].contains(&self.illum);
+        // Models 3, 5 and 8 add ray traced reflections
+        let reflective = [3, 5, 8].contains(&self.illum);
+
+        if transparent {
+            Arc::new(Dielectric::new(self.refractive_index))
+        } else if reflective {
+            // Rough approximation of a Phong exponent as fuzziness
+            let fuzz = (2.0 / (self.shininess + 2.0)).sqrt();
+            let albedo = self.specular;
+            Arc::new(Metal::new(
+                Arc::new(SolidColor::new(albedo.x, albedo.y, albedo.z)),
+                fuzz,
+            ))
+        } else {
+            let albedo = self.diffuse;
+            let texture = match &self.diffuse_map {
+                Some(texture) => Arc::clone(texture),
+                None => Arc::new(SolidColor::new(albedo.x, albedo.y, albedo.z)),
+            };
+            Arc::new(Lambertian::new(texture))
+        }
+    }
+}
+
+fn load_mtl(path: &Path) -> Result<HashMap<String, Arc<dyn Material>>, ObjError> {
+    let file = File::open(path).map_err(|err| ObjError::Io(path.to_owned(), err))?;
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut parsed: Vec<(String, MtlMaterial)> = Vec::new();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|err| ObjError::Io(path.to_owned(), err))?;
+        let mut parser = LineParser::new(path, index + 1, &line);
+
+        let keyword = match parser.keyword() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        if keyword == "newmtl" {
+            parsed.push((parser.name()?.to_owned(), MtlMaterial::default()));
+            continue;
+        }
+
+        let current = match parsed.last_mut() {
+            Some((_, current)) => current,
+            None => return Err(parser.error("material property before any `newmtl`")),
+        };
+        match keyword {
+            "Kd" => current.diffuse = parser.vec3()?,
+            "Ks" => current.specular = parser.vec3()?,
+            "Ns" => current.shininess = parser.value()?,
+            "Ni" => current.refractive_index = parser.value()?,
+            "d" => current.dissolve = parser.value()?,
+            "Tr" => current.dissolve = 1.0 - parser.value::<f32>()?,
+            "illum" => current.illum = parser.value()?,
+            "map_Kd" => {
+                let image = texture_map(&mut parser, dir)?;
+                current.diffuse_map = Some(Arc::new(image));
+            }
+            _ => {}
+        }
+    }
+
+    Ok(parsed
+        .into_iter()
+        .map(|(name, mtl)| (name, mtl.to_material()))
+        .collect())
+}
+
+// Load the image for a `map_` statement. OBJ textures repeat unless told to
+// clamp, and `-s` and `-o` scale and move the texture coordinates. The file
+// name comes last, after any options.
+fn texture_map(parser: &mut LineParser, dir: &Path) -> Result<Image, ObjError> {
+    let tokens: Vec<&str> = parser.rest().collect();
+    let (name, options) = tokens
+        .split_last()
+        .ok_or_else(|| parser.error("missing file"))?;
+
+    let mut wrap = Wrap::Repeat;
+    let mut scale = (1.0, 1.0);
+    let mut offset = (0.0, 0.0);
+    let mut i = 0;
+    while i < options.len() {
+        let option = options[i];
+        i += 1;
+        // Options take a variable number of arguments, which run up to the
+        // next option. Negative numbers are arguments too.
+        let start = i;
+        while options
+            .get(i)
+            .is_some_and(|a| !a.starts_with('-') || a.parse::<f32>().is_ok())
+        {
+            i += 1;
+        }
+        let args = &options[start..i];
+        let number = |n: usize, default: f32| {
+            args.get(n).map_or(Ok(default), |a| {
+                a.parse::<f32>()
+                    .map_err(|_| parser.error(&format!("invalid number `{}`", a)))
+            })
+        };
+        match option {
+            "-s" => scale = (number(0, 1.0)?, number(1, 1.0)?),
+            "-o" => offset = (number(0, 0.0)?, number(1, 0.0)?),
+            "-clamp" if args.first() == Some(&"on") => wrap = Wrap::Clamp,
+            _ => {}
+        }
+    }
+
+    let image = Image::load(&dir.join(name), ColorSpace::Srgb)
+        .map_err(|err| parser.error(&err.to_string()))?;
+    Ok(image
+        .with_filter(Filter::Trilinear)
+        .with_wrap(wrap)
+        .with_transform(scale, offset))
+}
+
+// Indices of the position, texture coordinate and normal of a face corner
+type FaceCorner = (usize, Option<usize>, Option<usize>);
+
+// Splits up a single line of an OBJ or MTL file, keeping track of where it
+// came from for error messages
+struct LineParser<'a> {
+    path: &'a Path,
+    line: usize,
+    tokens: SplitWhitespace<'a>,
+}
+
+impl<'a> LineParser<'a> {
+    fn new(path: &'a Path, line: usize, text: &'a str) -> Self {
+        // Everything after a `#` is a comment
+        let text = text.split('#').next().unwrap_or("");
+        LineParser {
+            path,
+            line,
+            tokens: text.split_whitespace(),
+        }
+    }
+
+    fn error(&self, msg: &str) -> ObjError {
+        ObjError::Parse {
+            path: self.path.to_owned(),
+            line: self.line,
+            msg: msg.to_owned(),
+        }
+    }
+
+    fn keyword(&mut self) -> Option<&'a str> {
+        self.tokens.next()
+    }
+
+    fn rest(&mut self) -> SplitWhitespace<'a> {
+        self.tokens.clone()
+    }
+
+    fn name(&mut self) -> Result<&'a str, ObjError> {
+        self.tokens.next().ok_or_else(|| self.error("missing name"))
+    }
+
+    fn optional_value<T: FromStr>(&mut self) -> Result<Option<T>, ObjError> {
+        match self.tokens.next() {
+            Some(token) => match token.parse() {
+                Ok(val) => Ok(Some(val)),
+                Err(_) => Err(self.error(&format!("invalid number `{}`", token))),
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn value<T: FromStr>(&mut self) -> Result<T, ObjError> {
+        self.optional_value()?
+            .ok_or_else(|| self.error("missing value"))
+    }
+
+    fn vec3(&mut self) -> Result<Vec3, ObjError> {
+        Ok(Vec3::new(self.value()?, self.value()?, self.value()?))
+    }
+
+    // Parse the next `v`, `v/vt`, `v//vn` or `v/vt/vn` corner of a face into
+    // zero based indices
+    fn face_corner(
+        &mut self,
+        positions: usize,
+        texcoords: usize,
+        normals: usize,
+    ) -> Result<Option<FaceCorner>, ObjError> {
+        let token = match self.tokens.next() {
+            Some(token) => token,
+            None => return Ok(None),
+        };
+
+        let mut parts = token.split('/');
+        let position = self.index(parts.next(), positions)?;
+        let position = position.ok_or_else(|| self.error("face corner missing a vertex"))?;
+        let texcoord = self.index(parts.next(), texcoords)?;
+        let normal = self.index(parts.next(), normals)?;
+
+        Ok(Some((position, texcoord, normal)))
+    }
+
+    // Indices start at 1, negative ones count back from the end of the list
+    fn index(&self, part: Option<&str>, len: usize) -> Result<Option<usize>, ObjError> {
+        let part = match part {
+            Some(part) if !part.is_empty() => part,
+            _ => return Ok(None),
+        };
+        let index: i64 = part
+            .parse()
+            .map_err(|_| self.error(&format!("invalid index `{}`", part)))?;
+
+        let resolved = if index < 0 {
+            len as i64 + index
+        } else {
+            index - 1
+        };
+        if resolved < 0 || resolved >= len as i64 {
+            return Err(self.error(&format!("index {} out of range", index)));
+        }
+        Ok(Some(resolved as usize))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, sync::Arc};
+
+    use crate::{
+        material::Lambertian,
+        ray::Ray,
+        shapes::{Hittable, Mesh},
+        vector::Vec3,
+    };
+
+    #[test]
+    fn load_obj() {
+        let dir =
+            std::env::temp_dir().join(format!("simple-ray-tracer-obj-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("quad.mtl"),
+            "newmtl mirror\nKs 0.9 0.9 0.9\nNs 500\nillum 3\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("quad.obj"),
+            "# A unit quad facing +z\n\
+             mtllib quad.mtl\n\
+             v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n\
+             vt 0 0\nvt 1 0\nvt 1 1\nvt 0 1\n\
+             vn 0 0 1\n\
+             usemtl mirror\n\
+             f 1/1/1 2/2/1 3/3/1 -1/-1/-1\n",
+        )
+        .unwrap();
+
+        let mesh = Mesh::load_obj(&dir.join("quad.obj"), Arc::new(Lambertian::default())).unwrap();
+        let bbox = mesh.bounding_box(0.0, 0.0).unwrap();
+        assert!((bbox.max.x - 1.0).abs() < 1e-3 && (bbox.max.y - 1.0).abs() < 1e-3);
+
+        let ray = Ray::new(Vec3::new(0.25, 0.75, 1.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        let hit = mesh.hit(&ray, 0.001, f32::MAX).unwrap();
+        assert!((hit.u - 0.25).abs() < 1e-5 && (hit.v - 0.75).abs() < 1e-5);
+        assert_eq!(hit.normal, Vec3::new(0.0, 0.0, 1.0));
+
+        // A material that isn't in the library falls back to the default
+        // rather than failing the whole mesh
+        fs::write(
+            dir.join("missing.obj"),
+            "v 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl nowhere\nf 1 2 3\nusemtl nowhere\nf 3 2 1\n",
+        )
+        .unwrap();
+        let mesh =
+            Mesh::load_obj(&dir.join("missing.obj"), Arc::new(Lambertian::default())).unwrap();
+        assert_eq!(mesh.warnings.len(), 1);
+        assert!(mesh.warnings[0].contains("unknown material `nowhere`"));
+
+        fs::write(dir.join("bad.obj"), "v 0 0 0\nf 1 2 3\n").unwrap();
+        let err = Mesh::load_obj(&dir.join("bad.obj"), Arc::new(Lambertian::default()));
+        assert!(err.is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,206 @@
+mod constant_medium;
+mod cuboid;
+mod mesh;
+mod rect;
+mod sphere;
+mod transform;
+mod triangle;
+
+pub use constant_medium::ConstantMedium;
+pub use cuboid::Cuboid;
+pub use mesh::{Mesh, ObjError};
+pub use rect::{FlipNormals, XYRect, XZRect, YZRect};
+pub use sphere::{MSphere, Sphere};
+pub use transform::{RotateX, RotateY, RotateZ, Transform, Translate};
+pub use triangle::Triangle;
+
+use std::sync::Arc;
+
+use crate::{
+    aabb::{surrounding_box, Aabb},
+    ray::{Ray, RayHit},
+    sampler::Sampler,
+    vector::Vec3,
+};
+
+pub trait Hittable: Send + Sync {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<RayHit>;
+    // Return a box enclosing the object over the time interval `time_0` to
+    // `time_1`, or `None` if the object is unbounded
+    fn bounding_box(&self, time_0: f32, time_1: f32) -> Option<Aabb>;
+
+    // Probability density, per unit solid angle, of `random` picking the
+    // direction of `ray` from its origin. Every point on the object along
+    // the ray counts, whether or not something else is in the way, since
+    // `random` can pick hidden points too. Objects that can't be sampled
+    // always give zero.
+    fn pdf_value(&self, _ray: &Ray) -> f32 {
+        0.0
+    }
+
+    // Direction from `origin` to a random point on the object, used to aim
+    // rays at lights
+    fn random(&self, _origin: Vec3, _sampler: &mut dyn Sampler) -> Vec3 {
+        Vec3::new(1.0, 0.0, 0.0)
+    }
+}
+
+impl Hittable for Vec<Box<dyn Hittable>> {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<RayHit> {
+        let mut hit: Option<RayHit> = None;
+
+        for object in self.iter() {
+            if let Some(object_hit) = object.hit(ray, t_min, t_max) {
+                match hit.clone() {
+                    // Check if the new hit is closer than the previous hit
+                    Some(prev) => {
+                        if object_hit.t < prev.t {
+                            hit = Some(object_hit);
+                        }
+                    }
+                    None => {
+                        hit = Some(object_hit);
+                    }
+                }
+            }
+        }
+
+        hit
+    }
+
+    fn bounding_box(&self, time_0: f32, time_1: f32) -> Option<Aabb> {
+        let mut objects = self.iter();
+        let mut bbox = objects.next()?.bounding_box(time_0, time_1)?;
+        for object in objects {
+            bbox = surrounding_box(&bbox, &object.bounding_box(time_0, time_1)?);
+        }
+        Some(bbox)
+    }
+
+    // Each object is equally likely to be picked
+    fn pdf_value(&self, ray: &Ray) -> f32 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        let total: f32 = self.iter().map(|object| object.pdf_value(ray)).sum();
+        total / self.len() as f32
+    }
+
+    fn random(&self, origin: Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        let index = (sampler.get_1d() * self.len() as f32) as usize;
+        self[index.min(self.len() - 1)].random(origin, sampler)
+    }
+}
+
+// Lets a single object be shared between several instance wrappers
+impl Hittable for Arc<dyn Hittable> {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<RayHit> {
+        self.as_ref().hit(ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self, time_0: f32, time_1: f32) -> Option<Aabb> {
+        self.as_ref().bounding_box(time_0, time_1)
+    }
+
+    fn pdf_value(&self, ray: &Ray) -> f32 {
+        self.as_ref().pdf_value(ray)
+    }
+
+    fn random(&self, origin: Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        self.as_ref().random(origin, sampler)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{f32::consts::PI, sync::Arc};
+
+    use crate::{
+        material::Lambertian,
+        matrix::Mat4,
+        ray::Ray,
+        sampler::SamplerKind,
+        shapes::{Cuboid, Hittable, RotateY, Sphere, Transform, Translate, Triangle, XZRect},
+        utils::{random_unit_vector, seed_random},
+        vector::Vec3,
+    };
+
+    #[test]
+    fn light_sampling() {
+        seed_random(11);
+        let mut sampler = SamplerKind::Independent.build(0, 1);
+        let mat = Arc::new(Lambertian::default());
+        let rect = || Box::new(XZRect::new(-1.0, 1.0, -0.5, 0.5, 2.0, mat.clone()));
+        let stretch = Mat4::new([
+            [2.0, 0.5, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.3, 0.5, -1.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        let vertices = [
+            Vec3::new(-1.0, -2.0, -1.0),
+            Vec3::new(1.0, -2.0, -1.5),
+            Vec3::new(0.0, -1.5, 1.0),
+        ];
+        let lights: Vec<Box<dyn Hittable>> = vec![
+            Box::new(Sphere::new(Vec3::new(3.0, 0.0, 0.0), 1.0, mat.clone())),
+            Box::new(Translate::new(
+                Box::new(RotateY::new(rect(), 30.0)),
+                Vec3::new(0.0, 0.0, -1.0),
+            )),
+            Box::new(Transform::new(rect(), stretch).unwrap()),
+            Box::new(Triangle::new(vertices, mat.clone())),
+            Box::new(Cuboid::new(
+                Vec3::new(-2.5, -0.5, 0.5),
+                Vec3::new(-1.5, 0.5, 2.0),
+                mat.clone(),
+            )),
+        ];
+        let origin = Vec3::new(0.0, 0.0, 0.0);
+
+        let n = 50000;
+        for (i, light) in lights.iter().enumerate() {
+            // Integrating the density over every direction should give one,
+            // and the fraction of directions that hit it is its solid angle
+            let mut total = 0.0;
+            let mut hits = 0;
+            for _ in 0..n {
+                let ray = Ray::new(origin, random_unit_vector(sampler.as_mut()), 0.0);
+                total += light.pdf_value(&ray);
+                if light.hit(&ray, 0.001, f32::MAX).is_some() {
+                    hits += 1;
+                }
+            }
+            let integral = total * 4.0 * PI / n as f32;
+            assert!((integral - 1.0).abs() < 0.05, "light {}: {}", i, integral);
+
+            // Directions picked by `random` should agree with the density,
+            // which makes one over it average out to the solid angle
+            let mut inverse = 0.0;
+            for _ in 0..n {
+                let ray = Ray::new(origin, light.random(origin, sampler.as_mut()), 0.0);
+                let pdf = light.pdf_value(&ray);
+                assert!(pdf > 0.0, "light {}", i);
+                inverse += 1.0 / pdf;
+            }
+            let solid_angle = hits as f32 * 4.0 * PI / n as f32;
+            let estimate = inverse / n as f32;
+            assert!(
+                (estimate / solid_angle - 1.0).abs() < 0.05,
+                "light {}: {} {}",
+                i,
+                estimate,
+                solid_angle
+            );
+        }
+
+        // A list of lights picks each of them evenly
+        let mut total = 0.0;
+        for _ in 0..n {
+            let ray = Ray::new(origin, random_unit_vector(sampler.as_mut()), 0.0);
+            total += lights.pdf_value(&ray);
+        }
+        let integral = total * 4.0 * PI / n as f32;
+        assert!((integral - 1.0).abs() < 0.05, "{}", integral);
+    }
+}
@@ -0,0 +1,317 @@
+use std::sync::Arc;
+
+use crate::{
+    aabb::Aabb,
+    material::Material,
+    ray::{Ray, RayHit},
+    sampler::Sampler,
+    shapes::Hittable,
+    vector::{dot, Vec3},
+};
+
+// Rectangles lying in a plane perpendicular to one of the axes. Each faces
+// towards the positive end of that axis, wrap them in `FlipNormals` to make
+// them face the other way.
+
+#[derive(Clone)]
+pub struct XYRect {
+    x0: f32,
+    x1: f32,
+    y0: f32,
+    y1: f32,
+    k: f32,
+    mat: Arc<dyn Material>,
+}
+
+impl XYRect {
+    pub fn new(x0: f32, x1: f32, y0: f32, y1: f32, k: f32, mat: Arc<dyn Material>) -> Self {
+        XYRect {
+            x0,
+            x1,
+            y0,
+            y1,
+            k,
+            mat,
+        }
+    }
+}
+
+impl Hittable for XYRect {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<RayHit> {
+        let bounds = (self.x0, self.x1, self.y0, self.y1);
+        rect_hit(ray, t_min, t_max, (0, 1, 2), bounds, self.k, &self.mat)
+    }
+
+    fn bounding_box(&self, _time_0: f32, _time_1: f32) -> Option<Aabb> {
+        Some(Aabb::new(
+            Vec3::new(self.x0, self.y0, self.k - PADDING),
+            Vec3::new(self.x1, self.y1, self.k + PADDING),
+        ))
+    }
+
+    fn pdf_value(&self, ray: &Ray) -> f32 {
+        let hit = self.hit(ray, 0.001, f32::MAX);
+        rect_pdf(ray, hit, (self.x1 - self.x0) * (self.y1 - self.y0))
+    }
+
+    fn random(&self, origin: Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        let bounds = (self.x0, self.x1, self.y0, self.y1);
+        rect_random(origin, sampler, (0, 1, 2), bounds, self.k)
+    }
+}
+
+#[derive(Clone)]
+pub struct XZRect {
+    x0: f32,
+    x1: f32,
+    z0: f32,
+    z1: f32,
+    k: f32,
+    mat: Arc<dyn Material>,
+}
+
+impl XZRect {
+    pub fn new(x0: f32, x1: f32, z0: f32, z1: f32, k: f32, mat: Arc<dyn Material>) -> Self {
+        XZRect {
+            x0,
+            x1,
+            z0,
+            z1,
+            k,
+            mat,
+        }
+    }
+}
+
+impl Hittable for XZRect {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<RayHit> {
+        let bounds = (self.x0, self.x1, self.z0, self.z1);
+        rect_hit(ray, t_min, t_max, (0, 2, 1), bounds, self.k, &self.mat)
+    }
+
+    fn bounding_box(&self, _time_0: f32, _time_1: f32) -> Option<Aabb> {
+        Some(Aabb::new(
+            Vec3::new(self.x0, self.k - PADDING, self.z0),
+            Vec3::new(self.x1, self.k + PADDING, self.z1),
+        ))
+    }
+
+    fn pdf_value(&self, ray: &Ray) -> f32 {
+        let hit = self.hit(ray, 0.001, f32::MAX);
+        rect_pdf(ray, hit, (self.x1 - self.x0) * (self.z1 - self.z0))
+    }
+
+    fn random(&self, origin: Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        let bounds = (self.x0, self.x1, self.z0, self.z1);
+        rect_random(origin, sampler, (0, 2, 1), bounds, self.k)
+    }
+}
+
+#[derive(Clone)]
+pub struct YZRect {
+    y0: f32,
+    y1: f32,
+    z0: f32,
+    z1: f32,
+    k: f32,
+    mat: Arc<dyn Material>,
+}
+
+impl YZRect {
+    pub fn new(y0: f32, y1: f32, z0: f32, z1: f32, k: f32, mat: Arc<dyn Material>) -> Self {
+        YZRect {
+            y0,
+            y1,
+            z0,
+            z1,
+            k,
+            mat,
+        }
+    }
+}
+
+impl Hittable for YZRect {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<RayHit> {
+        let bounds = (self.y0, self.y1, self.z0, self.z1);
+        rect_hit(ray, t_min, t_max, (1, 2, 0), bounds, self.k, &self.mat)
+    }
+
+    fn bounding_box(&self, _time_0: f32, _time_1: f32) -> Option<Aabb> {
+        Some(Aabb::new(
+            Vec3::new(self.k - PADDING, self.y0, self.z0),
+            Vec3::new(self.k + PADDING, self.y1, self.z1),
+        ))
+    }
+
+    fn pdf_value(&self, ray: &Ray) -> f32 {
+        let hit = self.hit(ray, 0.001, f32::MAX);
+        rect_pdf(ray, hit, (self.y1 - self.y0) * (self.z1 - self.z0))
+    }
+
+    fn random(&self, origin: Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        let bounds = (self.y0, self.y1, self.z0, self.z1);
+        rect_random(origin, sampler, (1, 2, 0), bounds, self.k)
+    }
+}
+
+// Rectangles have no thickness so their bounding boxes are padded slightly
+// along the axis they're perpendicular to
+const PADDING: f32 = 0.0001;
+
+// Shared intersection for all three rectangles. `a` and `b` are the axes the
+// rectangle spans, which the hit's u and v follow, and `k` is the axis it's
+// perpendicular to.
+fn rect_hit(
+    ray: &Ray,
+    t_min: f32,
+    t_max: f32,
+    (a, b, k): (usize, usize, usize),
+    (a0, a1, b0, b1): (f32, f32, f32, f32),
+    plane: f32,
+    mat: &Arc<dyn Material>,
+) -> Option<RayHit> {
+    let t = (plane - ray.origin[k]) / ray.dir[k];
+    // Also rejects the NaN from a ray lying in the plane
+    if !(t > t_min && t < t_max) {
+        return None;
+    }
+
+    let point = ray.point_at_parameter(t);
+    if point[a] < a0 || point[a] > a1 || point[b] < b0 || point[b] > b1 {
+        return None;
+    }
+
+    let u = (point[a] - a0) / (a1 - a0);
+    let v = (point[b] - b0) / (b1 - b0);
+    let normal = match k {
+        0 => Vec3::new(1.0, 0.0, 0.0),
+        1 => Vec3::new(0.0, 1.0, 0.0),
+        _ => Vec3::new(0.0, 0.0, 1.0),
+    };
+
+    let mut hit = RayHit::new(t, u, v, point, normal, Arc::clone(mat));
+    hit.uv_scale = 1.0 / ((a1 - a0) * (b1 - b0)).sqrt();
+    Some(hit)
+}
+
+// Density of hitting the rectangle at `hit` by picking points evenly over
+// its area, converted to a density over the directions seen from the ray's
+// origin
+fn rect_pdf(ray: &Ray, hit: Option<RayHit>, area: f32) -> f32 {
+    let hit = match hit {
+        Some(hit) => hit,
+        None => return 0.0,
+    };
+    let distance_sq = hit.t * hit.t * dot(&ray.dir, &ray.dir);
+    let cosine = (dot(&ray.dir, &hit.normal) / ray.dir.get_mag()).abs();
+    if cosine == 0.0 {
+        return 0.0;
+    }
+    distance_sq / (cosine * area)
+}
+
+fn rect_random(
+    origin: Vec3,
+    sampler: &mut dyn Sampler,
+    (a, b, k): (usize, usize, usize),
+    (a0, a1, b0, b1): (f32, f32, f32, f32),
+    plane: f32,
+) -> Vec3 {
+    let (s, t) = sampler.get_2d();
+    let mut point = [0.0; 3];
+    point[a] = a0 + s * (a1 - a0);
+    point[b] = b0 + t * (b1 - b0);
+    point[k] = plane;
+    Vec3::from(point) - origin
+}
+
+// Turn an object inside out by reversing its normals
+pub struct FlipNormals {
+    object: Box<dyn Hittable>,
+}
+
+impl FlipNormals {
+    pub fn new(object: Box<dyn Hittable>) -> Self {
+        FlipNormals { object }
+    }
+}
+
+impl Hittable for FlipNormals {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<RayHit> {
+        let mut hit = self.object.hit(ray, t_min, t_max)?;
+        hit.normal = -hit.normal;
+        Some(hit)
+    }
+
+    fn bounding_box(&self, time_0: f32, time_1: f32) -> Option<Aabb> {
+        self.object.bounding_box(time_0, time_1)
+    }
+
+    fn pdf_value(&self, ray: &Ray) -> f32 {
+        self.object.pdf_value(ray)
+    }
+
+    fn random(&self, origin: Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        self.object.random(origin, sampler)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        material::Lambertian,
+        ray::Ray,
+        shapes::{FlipNormals, Hittable, XYRect, XZRect, YZRect},
+        vector::Vec3,
+    };
+
+    #[test]
+    fn hit_and_miss() {
+        let mat = Arc::new(Lambertian::default());
+        // Unit squares at 2 along each axis, shot at from the origin
+        let rects: Vec<(Box<dyn Hittable>, Vec3)> = vec![
+            (
+                Box::new(XYRect::new(-0.5, 0.5, -0.5, 0.5, 2.0, mat.clone())),
+                Vec3::new(0.0, 0.0, 1.0),
+            ),
+            (
+                Box::new(XZRect::new(-0.5, 0.5, -0.5, 0.5, 2.0, mat.clone())),
+                Vec3::new(0.0, 1.0, 0.0),
+            ),
+            (
+                Box::new(YZRect::new(-0.5, 0.5, -0.5, 0.5, 2.0, mat)),
+                Vec3::new(1.0, 0.0, 0.0),
+            ),
+        ];
+
+        for (rect, axis) in &rects {
+            let origin = Vec3::new(0.0, 0.0, 0.0);
+            let hit = rect.hit(&Ray::new(origin, *axis, 0.0), 0.001, f32::MAX);
+            let hit = hit.expect("ray through the middle missed");
+            assert!((hit.t - 2.0).abs() < 1e-5);
+            assert!((hit.point - *axis * 2.0).get_mag() < 1e-5);
+            assert!((hit.normal - *axis).get_mag() < 1e-6);
+            assert!((hit.u - 0.5).abs() < 1e-5 && (hit.v - 0.5).abs() < 1e-5);
+
+            // Past the edge, pointing away, and beyond t_max
+            let off = Vec3::new(1.0, 1.0, 1.0) - *axis;
+            let ray = Ray::new(origin, *axis + off * 0.3, 0.0);
+            assert!(rect.hit(&ray, 0.001, f32::MAX).is_none());
+            let ray = Ray::new(origin, -*axis, 0.0);
+            assert!(rect.hit(&ray, 0.001, f32::MAX).is_none());
+            let ray = Ray::new(origin, *axis, 0.0);
+            assert!(rect.hit(&ray, 0.001, 1.5).is_none());
+        }
+    }
+
+    #[test]
+    fn flipped_normal() {
+        let mat = Arc::new(Lambertian::default());
+        let rect = FlipNormals::new(Box::new(XYRect::new(-1.0, 1.0, -1.0, 1.0, 0.0, mat)));
+        let ray = Ray::new(Vec3::new(0.2, 0.3, 5.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        let hit = rect.hit(&ray, 0.001, f32::MAX).unwrap();
+        assert!((hit.normal - Vec3::new(0.0, 0.0, -1.0)).get_mag() < 1e-6);
+    }
+}
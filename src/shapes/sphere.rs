@@ -4,39 +4,15 @@ use std::{
 };
 
 use crate::{
+    aabb::{surrounding_box, Aabb},
     material::Material,
     ray::{Ray, RayHit},
-    vector::{dot, Vec3},
+    sampler::Sampler,
+    shapes::Hittable,
+    utils::random_unit_vector,
+    vector::{dot, Onb, Vec3},
 };
 
-pub trait Hittable {
-    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<RayHit>;
-}
-
-impl Hittable for Vec<Box<dyn Hittable>> {
-    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<RayHit> {
-        let mut hit: Option<RayHit> = None;
-
-        for object in self.iter() {
-            if let Some(object_hit) = object.hit(ray, t_min, t_max) {
-                match hit.clone() {
-                    // Check if the new hit is closer than the previous hit
-                    Some(prev) => {
-                        if object_hit.t < prev.t {
-                            hit = Some(object_hit);
-                        }
-                    }
-                    None => {
-                        hit = Some(object_hit);
-                    }
-                }
-            }
-        }
-
-        hit
-    }
-}
-
 #[derive(Clone)]
 pub struct Sphere {
     center: Vec3,
@@ -56,7 +32,7 @@ impl Sphere {
 
 impl Hittable for Sphere {
     fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<RayHit> {
-        let ray = ray.clone();
+        let ray = *ray;
         let oc = ray.origin - self.center;
         let a = dot(&ray.dir, &ray.dir);
         let b = dot(&oc, &ray.dir);
@@ -75,6 +51,47 @@ impl Hittable for Sphere {
         }
         None
     }
+
+    fn bounding_box(&self, _time_0: f32, _time_1: f32) -> Option<Aabb> {
+        Some(sphere_box(self.center, self.radius))
+    }
+
+    fn pdf_value(&self, ray: &Ray) -> f32 {
+        if self.hit(ray, 0.001, f32::MAX).is_none() {
+            return 0.0;
+        }
+        match cone_cos_max(ray.origin, self.center, self.radius) {
+            Some(cos_max) => 1.0 / (2.0 * PI * (1.0 - cos_max)),
+            None => 1.0 / (4.0 * PI),
+        }
+    }
+
+    // Directions are picked evenly from the cone the sphere fills as seen
+    // from `origin`, or from every direction if `origin` is inside it
+    fn random(&self, origin: Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        let cos_max = match cone_cos_max(origin, self.center, self.radius) {
+            Some(cos_max) => cos_max,
+            None => return random_unit_vector(sampler),
+        };
+        let (u1, u2) = sampler.get_2d();
+        let z = 1.0 + u1 * (cos_max - 1.0);
+        let r = (1.0 - z * z).max(0.0).sqrt();
+        let phi = 2.0 * PI * u2;
+
+        let basis = Onb::new((self.center - origin).get_unit());
+        basis.local_to_world(Vec3::new(r * phi.cos(), r * phi.sin(), z))
+    }
+}
+
+// Cosine of the angle between the centre of the sphere and its edge as seen
+// from `origin`, `None` if `origin` is inside the sphere
+fn cone_cos_max(origin: Vec3, center: Vec3, radius: f32) -> Option<f32> {
+    let offset = center - origin;
+    let distance_sq = dot(&offset, &offset);
+    if distance_sq <= radius * radius {
+        return None;
+    }
+    Some((1.0 - radius * radius / distance_sq).sqrt())
 }
 
 // A moving sphere
@@ -117,7 +134,7 @@ impl MSphere {
 
 impl Hittable for MSphere {
     fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<RayHit> {
-        let ray = ray.clone();
+        let ray = *ray;
         let oc = ray.origin - self.center(ray.time);
         let a = dot(&ray.dir, &ray.dir);
         let b = dot(&oc, &ray.dir);
@@ -136,6 +153,18 @@ impl Hittable for MSphere {
         }
         None
     }
+
+    fn bounding_box(&self, time_0: f32, time_1: f32) -> Option<Aabb> {
+        // Sweep the sphere's box across the whole interval
+        let box_0 = sphere_box(self.center(time_0), self.radius);
+        let box_1 = sphere_box(self.center(time_1), self.radius);
+        Some(surrounding_box(&box_0, &box_1))
+    }
+}
+
+fn sphere_box(center: Vec3, radius: f32) -> Aabb {
+    let r = Vec3::new(radius, radius, radius);
+    Aabb::new(center - r, center + r)
 }
 
 fn create_ray_hit(
@@ -147,7 +176,7 @@ fn create_ray_hit(
 ) -> Option<RayHit> {
     let normal = (ray.point_at_parameter(temp) - center) / radius;
     let (u, v) = get_sphere_uv(normal);
-    let ray_hit = RayHit::new(
+    let mut ray_hit = RayHit::new(
         temp,
         u,
         v,
@@ -155,6 +184,9 @@ fn create_ray_hit(
         normal,
         Arc::clone(mat),
     );
+    // u wraps around the equator and v runs pole to pole, use the geometric
+    // mean of the two
+    ray_hit.uv_scale = 1.0 / (2.0f32.sqrt() * PI * radius);
     Some(ray_hit)
 }
 
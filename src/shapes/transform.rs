@@ -0,0 +1,263 @@
+use crate::{
+    aabb::Aabb,
+    matrix::Mat4,
+    ray::{Ray, RayHit},
+    sampler::Sampler,
+    shapes::Hittable,
+    vector::{cross, dot, Vec3},
+};
+
+// Instance wrappers that place an object somewhere else in the scene. Rays are
+// moved into the object's own space to test for hits, and the hit point and
+// normal are moved back out again. To use the same object in several places
+// wrap an `Arc<dyn Hittable>` rather than copying it.
+
+pub struct Translate {
+    object: Box<dyn Hittable>,
+    offset: Vec3,
+}
+
+impl Translate {
+    pub fn new(object: Box<dyn Hittable>, offset: Vec3) -> Self {
+        Translate { object, offset }
+    }
+}
+
+impl Hittable for Translate {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<RayHit> {
+        let moved = Ray::new(ray.origin - self.offset, ray.dir, ray.time);
+        let mut hit = self.object.hit(&moved, t_min, t_max)?;
+        hit.point += self.offset;
+        Some(hit)
+    }
+
+    fn bounding_box(&self, time_0: f32, time_1: f32) -> Option<Aabb> {
+        let bbox = self.object.bounding_box(time_0, time_1)?;
+        Some(Aabb::new(bbox.min + self.offset, bbox.max + self.offset))
+    }
+
+    fn pdf_value(&self, ray: &Ray) -> f32 {
+        let moved = Ray::new(ray.origin - self.offset, ray.dir, ray.time);
+        self.object.pdf_value(&moved)
+    }
+
+    fn random(&self, origin: Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        self.object.random(origin - self.offset, sampler)
+    }
+}
+
+// Rotation of the object about one of the axes, by an angle in degrees
+pub struct Rotate<const AXIS: usize> {
+    object: Box<dyn Hittable>,
+    sin_theta: f32,
+    cos_theta: f32,
+}
+
+pub type RotateX = Rotate<0>;
+pub type RotateY = Rotate<1>;
+pub type RotateZ = Rotate<2>;
+
+impl<const AXIS: usize> Rotate<AXIS> {
+    pub fn new(object: Box<dyn Hittable>, angle: f32) -> Self {
+        let (sin_theta, cos_theta) = angle.to_radians().sin_cos();
+        Rotate {
+            object,
+            sin_theta,
+            cos_theta,
+        }
+    }
+
+    // Rotate `v` about the axis, pass `-sin_theta` to rotate the other way
+    fn rotate(&self, v: Vec3, sin_theta: f32) -> Vec3 {
+        // The two axes perpendicular to the one being rotated about
+        let (b, c) = ((AXIS + 1) % 3, (AXIS + 2) % 3);
+        let mut rotated = [v.x, v.y, v.z];
+        rotated[b] = self.cos_theta * v[b] - sin_theta * v[c];
+        rotated[c] = sin_theta * v[b] + self.cos_theta * v[c];
+        rotated.into()
+    }
+}
+
+impl<const AXIS: usize> Hittable for Rotate<AXIS> {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<RayHit> {
+        let origin = self.rotate(ray.origin, -self.sin_theta);
+        let dir = self.rotate(ray.dir, -self.sin_theta);
+        let rotated = Ray::new(origin, dir, ray.time);
+
+        let mut hit = self.object.hit(&rotated, t_min, t_max)?;
+        hit.point = self.rotate(hit.point, self.sin_theta);
+        hit.normal = self.rotate(hit.normal, self.sin_theta);
+        Some(hit)
+    }
+
+    fn bounding_box(&self, time_0: f32, time_1: f32) -> Option<Aabb> {
+        let bbox = self.object.bounding_box(time_0, time_1)?;
+        Some(transform_box(&bbox, |p| self.rotate(p, self.sin_theta)))
+    }
+
+    // Rotating doesn't change solid angles so the density carries over
+    fn pdf_value(&self, ray: &Ray) -> f32 {
+        let origin = self.rotate(ray.origin, -self.sin_theta);
+        let dir = self.rotate(ray.dir, -self.sin_theta);
+        self.object.pdf_value(&Ray::new(origin, dir, ray.time))
+    }
+
+    fn random(&self, origin: Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        let origin = self.rotate(origin, -self.sin_theta);
+        self.rotate(self.object.random(origin, sampler), self.sin_theta)
+    }
+}
+
+// General affine transform given as a matrix taking points in the object's
+// space to the scene
+pub struct Transform {
+    object: Box<dyn Hittable>,
+    matrix: Mat4,
+    inverse: Mat4,
+    // Normals have to be transformed by the inverse transpose to stay
+    // perpendicular to the surface once it's been scaled or sheared
+    normal_matrix: Mat4,
+    // Determinant of the inverse, how much it scales volumes by
+    inverse_det: f32,
+}
+
+impl Transform {
+    // Returns `None` if the matrix can't be inverted
+    pub fn new(object: Box<dyn Hittable>, matrix: Mat4) -> Option<Self> {
+        let inverse = matrix.inverse()?;
+        let [x, y, z] = [
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+        ]
+        .map(|axis| inverse.transform_vector(axis));
+        Some(Transform {
+            object,
+            matrix,
+            inverse,
+            normal_matrix: inverse.transpose(),
+            inverse_det: dot(&x, &cross(&y, &z)),
+        })
+    }
+}
+
+impl Hittable for Transform {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<RayHit> {
+        // The direction isn't normalised so `t` means the same in both spaces
+        let origin = self.inverse.transform_point(ray.origin);
+        let dir = self.inverse.transform_vector(ray.dir);
+        let transformed = Ray::new(origin, dir, ray.time);
+
+        let mut hit = self.object.hit(&transformed, t_min, t_max)?;
+        hit.point = self.matrix.transform_point(hit.point);
+        hit.normal = self.normal_matrix.transform_vector(hit.normal).get_unit();
+        Some(hit)
+    }
+
+    fn bounding_box(&self, time_0: f32, time_1: f32) -> Option<Aabb> {
+        let bbox = self.object.bounding_box(time_0, time_1)?;
+        Some(transform_box(&bbox, |p| self.matrix.transform_point(p)))
+    }
+
+    // Unlike rotating, scaling and shearing stretch solid angles unevenly.
+    // A unit direction `w` maps to `A w` in the object's space, and a small
+    // patch of directions around it covers |det A| / |A w|³ times the solid
+    // angle there, where `A` is the inverse.
+    fn pdf_value(&self, ray: &Ray) -> f32 {
+        let origin = self.inverse.transform_point(ray.origin);
+        let dir = self.inverse.transform_vector(ray.dir.get_unit());
+        let density = self.object.pdf_value(&Ray::new(origin, dir, ray.time));
+        density * self.inverse_det.abs() / dir.get_mag().powi(3)
+    }
+
+    fn random(&self, origin: Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        let local = self.inverse.transform_point(origin);
+        self.matrix
+            .transform_vector(self.object.random(local, sampler))
+    }
+}
+
+// Box enclosing all eight corners of `bbox` once they've been transformed
+fn transform_box<F>(bbox: &Aabb, transform: F) -> Aabb
+where
+    F: Fn(Vec3) -> Vec3,
+{
+    let mut min = Vec3::new(f32::MAX, f32::MAX, f32::MAX);
+    let mut max = Vec3::new(f32::MIN, f32::MIN, f32::MIN);
+
+    for i in 0..8 {
+        let corner = Vec3::new(
+            if i & 1 == 0 { bbox.min.x } else { bbox.max.x },
+            if i & 2 == 0 { bbox.min.y } else { bbox.max.y },
+            if i & 4 == 0 { bbox.min.z } else { bbox.max.z },
+        );
+        let p = transform(corner);
+        min = Vec3::new(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z));
+        max = Vec3::new(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z));
+    }
+
+    Aabb::new(min, max)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        material::Lambertian,
+        matrix::Mat4,
+        ray::Ray,
+        shapes::{Cuboid, Hittable, RotateY, Transform, Translate},
+        vector::Vec3,
+    };
+
+    fn cuboid() -> Box<dyn Hittable> {
+        let mat = Arc::new(Lambertian::default());
+        Box::new(Cuboid::new(
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(1.0, 2.0, 1.0),
+            mat,
+        ))
+    }
+
+    #[test]
+    fn wrappers_match_matrix() {
+        let offset = Vec3::new(2.0, 0.5, -1.0);
+        let wrapped = Translate::new(Box::new(RotateY::new(cuboid(), 30.0)), offset);
+        let matrix = Mat4::translation(offset) * Mat4::rotation_y(30.0);
+        let transformed = Transform::new(cuboid(), matrix).unwrap();
+
+        let origin = Vec3::new(0.0, 1.0, 8.0);
+        for i in 0..20 {
+            let target = Vec3::new(i as f32 * 0.25, 1.2, -1.0);
+            let ray = Ray::new(origin, target - origin, 0.0);
+
+            let a = wrapped.hit(&ray, 0.001, f32::MAX);
+            let b = transformed.hit(&ray, 0.001, f32::MAX);
+            match (a, b) {
+                (Some(a), Some(b)) => {
+                    assert!((a.t - b.t).abs() < 1e-4);
+                    assert!((a.normal - b.normal).get_mag() < 1e-4);
+                    assert!((a.point - b.point).get_mag() < 1e-3);
+                }
+                (None, None) => {}
+                _ => panic!("only one of the wrappers was hit"),
+            }
+        }
+    }
+
+    #[test]
+    fn scaled_normals() {
+        // Scaling keeps the faces axis aligned, so the normal on top should
+        // still point straight up and not be stretched
+        let matrix = Mat4::scale(Vec3::new(4.0, 0.5, 1.0));
+        let transformed = Transform::new(cuboid(), matrix).unwrap();
+        let bbox = transformed.bounding_box(0.0, 0.0).unwrap();
+        assert_eq!(bbox.max, Vec3::new(4.0, 1.0, 1.0));
+
+        let ray = Ray::new(Vec3::new(2.0, 5.0, 0.5), Vec3::new(0.0, -1.0, 0.0), 0.0);
+        let hit = transformed.hit(&ray, 0.001, f32::MAX).unwrap();
+        assert!((hit.t - 4.0).abs() < 1e-4);
+        assert!((hit.normal - Vec3::new(0.0, 1.0, 0.0)).get_mag() < 1e-4);
+    }
+}
@@ -0,0 +1,216 @@
+use std::sync::Arc;
+
+use crate::{
+    aabb::Aabb,
+    material::Material,
+    ray::{Ray, RayHit},
+    sampler::Sampler,
+    shapes::Hittable,
+    vector::{cross, dot, Vec3},
+};
+
+// Triangles are one sided for the purpose of normals, which face the side
+// the vertices appear anticlockwise from
+#[derive(Clone)]
+pub struct Triangle {
+    vertices: [Vec3; 3],
+    // Per vertex normals, interpolated across the face for smooth shading
+    normals: Option<[Vec3; 3]>,
+    // Per vertex texture coordinates, without them the barycentric
+    // coordinates are used instead
+    uvs: Option<[(f32, f32); 3]>,
+    mat: Arc<dyn Material>,
+}
+
+impl Triangle {
+    pub fn new(vertices: [Vec3; 3], mat: Arc<dyn Material>) -> Self {
+        Triangle {
+            vertices,
+            normals: None,
+            uvs: None,
+            mat,
+        }
+    }
+
+    pub fn with_normals(mut self, normals: [Vec3; 3]) -> Self {
+        self.normals = Some(normals);
+        self
+    }
+
+    pub fn with_uvs(mut self, uvs: [(f32, f32); 3]) -> Self {
+        self.uvs = Some(uvs);
+        self
+    }
+}
+
+impl Hittable for Triangle {
+    // Möller-Trumbore intersection
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<RayHit> {
+        let [v0, v1, v2] = self.vertices;
+        let edge_1 = v1 - v0;
+        let edge_2 = v2 - v0;
+
+        let p = cross(&ray.dir, &edge_2);
+        let det = dot(&edge_1, &p);
+        // Ray is parallel to the triangle, or the triangle is degenerate.
+        // Checked relative to the sizes involved so tiny and huge triangles
+        // are treated the same.
+        if det.abs() <= f32::EPSILON * edge_1.get_mag() * p.get_mag() {
+            return None;
+        }
+        let inv_det = 1.0 / det;
+
+        let s = ray.origin - v0;
+        let b1 = dot(&s, &p) * inv_det;
+        if !(0.0..=1.0).contains(&b1) {
+            return None;
+        }
+
+        let q = cross(&s, &edge_1);
+        let b2 = dot(&ray.dir, &q) * inv_det;
+        if b2 < 0.0 || b1 + b2 > 1.0 {
+            return None;
+        }
+
+        let t = dot(&edge_2, &q) * inv_det;
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+
+        let b0 = 1.0 - b1 - b2;
+        let normal = match self.normals {
+            Some([n0, n1, n2]) => (b0 * n0 + b1 * n1 + b2 * n2).get_unit(),
+            None => cross(&edge_1, &edge_2).get_unit(),
+        };
+        let ((u, v), uv_area) = match self.uvs {
+            Some([uv0, uv1, uv2]) => {
+                let uv = (
+                    b0 * uv0.0 + b1 * uv1.0 + b2 * uv2.0,
+                    b0 * uv0.1 + b1 * uv1.1 + b2 * uv2.1,
+                );
+                let area = ((uv1.0 - uv0.0) * (uv2.1 - uv0.1) - (uv2.0 - uv0.0) * (uv1.1 - uv0.1))
+                    .abs()
+                    / 2.0;
+                (uv, area)
+            }
+            None => ((b1, b2), 0.5),
+        };
+
+        let mut hit = RayHit::new(
+            t,
+            u,
+            v,
+            ray.point_at_parameter(t),
+            normal,
+            Arc::clone(&self.mat),
+        );
+        let area = cross(&edge_1, &edge_2).get_mag() / 2.0;
+        hit.uv_scale = (uv_area / area).sqrt();
+        Some(hit)
+    }
+
+    fn bounding_box(&self, _time_0: f32, _time_1: f32) -> Option<Aabb> {
+        let [v0, v1, v2] = self.vertices;
+        // Padded so triangles lying in an axis plane still have some volume
+        let pad = Vec3::new(PADDING, PADDING, PADDING);
+        let min = Vec3::new(
+            v0.x.min(v1.x).min(v2.x),
+            v0.y.min(v1.y).min(v2.y),
+            v0.z.min(v1.z).min(v2.z),
+        );
+        let max = Vec3::new(
+            v0.x.max(v1.x).max(v2.x),
+            v0.y.max(v1.y).max(v2.y),
+            v0.z.max(v1.z).max(v2.z),
+        );
+        Some(Aabb::new(min - pad, max + pad))
+    }
+
+    // Points are picked evenly over the area, and the density turned into
+    // one over directions using the flat face rather than any shading normals
+    fn pdf_value(&self, ray: &Ray) -> f32 {
+        let hit = match self.hit(ray, 0.001, f32::MAX) {
+            Some(hit) => hit,
+            None => return 0.0,
+        };
+        let [v0, v1, v2] = self.vertices;
+        let normal = cross(&(v1 - v0), &(v2 - v0));
+        let area = normal.get_mag() / 2.0;
+        let distance_sq = hit.t * hit.t * dot(&ray.dir, &ray.dir);
+        let cosine = (dot(&ray.dir, &normal) / (ray.dir.get_mag() * normal.get_mag())).abs();
+        if cosine == 0.0 {
+            return 0.0;
+        }
+        distance_sq / (cosine * area)
+    }
+
+    fn random(&self, origin: Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        // Folding the unit square onto the triangle like this keeps the
+        // points evenly spread
+        let (s, t) = sampler.get_2d();
+        let r = s.sqrt();
+        let [v0, v1, v2] = self.vertices;
+        (1.0 - r) * v0 + r * (1.0 - t) * v1 + r * t * v2 - origin
+    }
+}
+
+const PADDING: f32 = 0.0001;
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        material::Lambertian,
+        ray::Ray,
+        shapes::{Hittable, Triangle},
+        vector::Vec3,
+    };
+
+    fn triangle() -> Triangle {
+        Triangle::new(
+            [
+                Vec3::new(0.0, 0.0, 0.0),
+                Vec3::new(1.0, 0.0, 0.0),
+                Vec3::new(0.0, 1.0, 0.0),
+            ],
+            Arc::new(Lambertian::default()),
+        )
+    }
+
+    #[test]
+    fn barycentric_hit() {
+        let ray = Ray::new(Vec3::new(0.25, 0.5, 2.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        let hit = triangle().hit(&ray, 0.001, f32::MAX).unwrap();
+        assert!((hit.t - 2.0).abs() < 1e-6);
+        assert!((hit.u - 0.25).abs() < 1e-6);
+        assert!((hit.v - 0.5).abs() < 1e-6);
+        assert_eq!(hit.normal, Vec3::new(0.0, 0.0, 1.0));
+
+        // Outside the triangle, past the hypotenuse
+        let ray = Ray::new(Vec3::new(0.6, 0.6, 2.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        assert!(triangle().hit(&ray, 0.001, f32::MAX).is_none());
+
+        // Parallel to the triangle
+        let ray = Ray::new(Vec3::new(-1.0, 0.2, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        assert!(triangle().hit(&ray, 0.001, f32::MAX).is_none());
+    }
+
+    #[test]
+    fn smooth_normals_and_uvs() {
+        let up = Vec3::new(0.0, 0.0, 1.0);
+        let tilted = Vec3::new(1.0, 0.0, 1.0).get_unit();
+        let tri = triangle().with_normals([up, tilted, up]).with_uvs([
+            (0.0, 0.0),
+            (2.0, 0.0),
+            (0.0, 2.0),
+        ]);
+
+        let ray = Ray::new(Vec3::new(0.5, 0.25, 1.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        let hit = tri.hit(&ray, 0.001, f32::MAX).unwrap();
+        let expected = (0.25 * up + 0.5 * tilted + 0.25 * up).get_unit();
+        assert!((hit.normal - expected).get_mag() < 1e-6);
+        assert!((hit.u - 1.0).abs() < 1e-6);
+        assert!((hit.v - 0.5).abs() < 1e-6);
+    }
+}
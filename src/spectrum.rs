@@ -0,0 +1,87 @@
+use crate::{color::xyz_to_linear_srgb, vector::Vec3};
+
+// In spectral mode every path carries a single wavelength of light instead
+// of red, green and blue. Colours in the scene are still RGB and each channel
+// is treated as an even spread of light across the spectrum, so a path only
+// behaves differently for things that depend on the wavelength, like glass
+// that disperses light. What it finds is then weighted by how that
+// wavelength looks in RGB.
+
+// Range of wavelengths sampled in nanometres, roughly what the eye can see
+const SHORTEST: f32 = 380.0;
+const LONGEST: f32 = 780.0;
+
+// Pick a wavelength evenly across the visible range from `u` in [0, 1)
+pub fn sample_wavelength(u: f32) -> f32 {
+    SHORTEST + u * (LONGEST - SHORTEST)
+}
+
+// Average of `unscaled_weight` over the sampled range, taken at 1nm steps.
+// The test below checks these still match if anything above changes.
+const AVERAGE_WEIGHT: [f32; 3] = [0.440_446_7, 0.288_464_8, 0.273_241];
+
+// How much a sample at `wavelength` counts towards each RGB channel. The
+// colour matching functions are converted to sRGB and scaled so the weights
+// average to one over the range, which leaves anything that doesn't depend
+// on the wavelength the same colour it is in RGB mode.
+pub fn wavelength_weight(wavelength: f32) -> Vec3 {
+    let weight = unscaled_weight(wavelength);
+    let [r, g, b] = AVERAGE_WEIGHT;
+    Vec3::new(weight.x / r, weight.y / g, weight.z / b)
+}
+
+// Pure spectral colours are more saturated than sRGB can show, so the
+// channels that go negative are dropped rather than letting samples take
+// light away
+fn unscaled_weight(wavelength: f32) -> Vec3 {
+    let rgb = xyz_to_linear_srgb(color_matching(wavelength));
+    Vec3::new(rgb.x.max(0.0), rgb.y.max(0.0), rgb.z.max(0.0))
+}
+
+// The CIE 1931 standard observer's colour matching functions, using the
+// multi-lobe fit from Wyman, Sloan and Shirley's "Simple Analytic
+// Approximations to the CIE XYZ Color Matching Functions"
+fn color_matching(wavelength: f32) -> Vec3 {
+    // A Gaussian with a different width either side of its peak
+    let lobe = |peak: f32, below: f32, above: f32| {
+        let width = if wavelength < peak { below } else { above };
+        let t = (wavelength - peak) / width;
+        (-0.5 * t * t).exp()
+    };
+    Vec3::new(
+        1.056 * lobe(599.8, 37.9, 31.0) + 0.362 * lobe(442.0, 16.0, 26.7)
+            - 0.065 * lobe(501.1, 20.4, 26.2),
+        0.821 * lobe(568.8, 46.9, 40.5) + 0.286 * lobe(530.9, 16.3, 31.1),
+        1.217 * lobe(437.0, 11.8, 36.0) + 0.681 * lobe(459.0, 26.0, 13.8),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::spectrum::{color_matching, sample_wavelength, wavelength_weight};
+
+    #[test]
+    fn wavelength_weights() {
+        // The luminance curve peaks in the green at about 555nm
+        assert!((color_matching(555.0).y - 1.0).abs() < 0.02);
+
+        // Averaged over the spectrum it all comes back to white
+        let n = 4000;
+        let mut total = [0.0; 3];
+        for i in 0..n {
+            let weight = wavelength_weight(sample_wavelength((i as f32 + 0.5) / n as f32));
+            total[0] += weight.x / n as f32;
+            total[1] += weight.y / n as f32;
+            total[2] += weight.z / n as f32;
+        }
+        for channel in total.iter() {
+            assert!((channel - 1.0).abs() < 1e-3);
+        }
+
+        // Long wavelengths are red and short ones blue
+        let red = wavelength_weight(650.0);
+        assert!(red.x > red.y && red.x > red.z);
+        let blue = wavelength_weight(450.0);
+        assert!(blue.z > blue.x && blue.z > blue.y);
+    }
+}
@@ -1,30 +1,84 @@
-use rand::{thread_rng, Rng};
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg32;
 
-use crate::vector::{dot, Vec3};
+use std::{
+    cell::RefCell,
+    f32::consts::{FRAC_PI_2, FRAC_PI_4, PI},
+};
+
+use crate::{sampler::Sampler, vector::Vec3};
+
+thread_local! {
+    // Each thread has its own generator, randomly seeded unless `seed_random`
+    // is called
+    static RNG: RefCell<Pcg32> = RefCell::new(Pcg32::from_entropy());
+}
+
+// Reseed the current thread's generator so the numbers that follow are
+// repeatable
+pub fn seed_random(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = Pcg32::seed_from_u64(seed));
+}
+
+// Mix `values` into `seed` to get a new seed, so each pixel can have its own
+// repeatable stream of numbers whichever thread renders it. Uses the SplitMix64
+// finaliser, which spreads small changes in the input across every bit.
+pub fn hash_seed(seed: u64, values: &[u64]) -> u64 {
+    values.iter().fold(seed, |hash, val| {
+        let mut z = (hash ^ val).wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    })
+}
 
 pub fn gen_random() -> f32 {
     // Return random number between 0.0 and 1.0
-    thread_rng().gen()
+    RNG.with(|rng| rng.borrow_mut().gen())
 }
 
 pub fn gen_range(low: f32, high: f32) -> f32 {
     // Return random number between `low` and `high`
-    thread_rng().gen_range(low, high)
+    RNG.with(|rng| rng.borrow_mut().gen_range(low, high))
 }
 
-pub fn random_in_unit_sphere() -> Vec3 {
-    let mut p =
-        (2.0 * Vec3::new(gen_random(), gen_random(), gen_random())) - Vec3::new(1.0, 1.0, 1.0);
-    while dot(&p, &p) >= 1.0 {
-        p = (2.0 * Vec3::new(gen_random(), gen_random(), gen_random())) - Vec3::new(1.0, 1.0, 1.0);
-    }
-    p
+// Uniformly distributed point inside the unit sphere
+pub fn random_in_unit_sphere(sampler: &mut dyn Sampler) -> Vec3 {
+    let dir = random_unit_vector(sampler);
+    sampler.get_1d().cbrt() * dir
+}
+
+// Uniformly distributed point on the surface of the unit sphere, which is
+// also a direction picked evenly from every direction
+pub fn random_unit_vector(sampler: &mut dyn Sampler) -> Vec3 {
+    let (u1, u2) = sampler.get_2d();
+    let z = 1.0 - 2.0 * u1;
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * PI * u2;
+    Vec3::new(r * phi.cos(), r * phi.sin(), z)
+}
+
+// Direction in the hemisphere around the z axis, picked in proportion to the
+// cosine of its angle from the axis. Points spread evenly over the unit disk
+// are lifted up onto the hemisphere.
+pub fn random_cosine_direction(sampler: &mut dyn Sampler) -> Vec3 {
+    let p = random_in_unit_disk(sampler);
+    let z = (1.0 - p.x * p.x - p.y * p.y).max(0.0).sqrt();
+    Vec3::new(p.x, p.y, z)
 }
 
-pub fn random_in_unit_disk() -> Vec3 {
-    let mut p = (2.0 * Vec3::new(gen_random(), gen_random(), 0.0)) - Vec3::new(1.0, 1.0, 0.0);
-    while dot(&p, &p) >= 1.0 {
-        p = (2.0 * Vec3::new(gen_random(), gen_random(), 0.0)) - Vec3::new(1.0, 1.0, 0.0);
+// Uniformly distributed point inside the unit disk in the xy plane, using
+// Shirley and Chiu's concentric mapping so evenly spread samples stay that way
+pub fn random_in_unit_disk(sampler: &mut dyn Sampler) -> Vec3 {
+    let (u1, u2) = sampler.get_2d();
+    let (a, b) = (2.0 * u1 - 1.0, 2.0 * u2 - 1.0);
+    if a == 0.0 && b == 0.0 {
+        return Vec3::new(0.0, 0.0, 0.0);
     }
-    p
+    let (r, theta) = if a.abs() > b.abs() {
+        (a, FRAC_PI_4 * (b / a))
+    } else {
+        (b, FRAC_PI_2 - FRAC_PI_4 * (a / b))
+    };
+    Vec3::new(r * theta.cos(), r * theta.sin(), 0.0)
 }
@@ -1,6 +1,10 @@
-use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub};
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+use std::ops::{Add, AddAssign, Div, DivAssign, Index, Mul, MulAssign, Neg, Sub};
+
+// Stored in scene files as a plain `[x, y, z]` array
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(from = "[f32; 3]", into = "[f32; 3]")]
 pub struct Vec3 {
     pub x: f32,
     pub y: f32,
@@ -22,6 +26,18 @@ impl Vec3 {
     }
 }
 
+impl From<[f32; 3]> for Vec3 {
+    fn from(v: [f32; 3]) -> Vec3 {
+        Vec3::new(v[0], v[1], v[2])
+    }
+}
+
+impl From<Vec3> for [f32; 3] {
+    fn from(v: Vec3) -> [f32; 3] {
+        [v.x, v.y, v.z]
+    }
+}
+
 impl Add for Vec3 {
     type Output = Vec3;
 
@@ -116,6 +132,19 @@ impl Neg for Vec3 {
     }
 }
 
+impl Index<usize> for Vec3 {
+    type Output = f32;
+
+    fn index(&self, axis: usize) -> &f32 {
+        match axis {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            _ => panic!("Vec3 index out of range: {}", axis),
+        }
+    }
+}
+
 // Return dot product of two Vec3 vectors
 pub fn dot(a: &Vec3, b: &Vec3) -> f32 {
     (a.x * b.x) + (a.y * b.y) + (a.z * b.z)
@@ -129,6 +158,39 @@ pub fn cross(a: &Vec3, b: &Vec3) -> Vec3 {
     Vec3::new(x, y, z)
 }
 
+// Orthonormal basis around the unit vector `w`, for working with directions
+// relative to a surface normal
+#[derive(Clone, Copy, Debug)]
+pub struct Onb {
+    pub u: Vec3,
+    pub v: Vec3,
+    pub w: Vec3,
+}
+
+impl Onb {
+    pub fn new(w: Vec3) -> Self {
+        // Start from whichever axis is furthest from lining up with `w`
+        let a = if w.x.abs() > 0.9 {
+            Vec3::new(0.0, 1.0, 0.0)
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let v = cross(&w, &a).get_unit();
+        let u = cross(&w, &v);
+        Onb { u, v, w }
+    }
+
+    // Turn a vector given in terms of the basis into a world space one
+    pub fn local_to_world(&self, a: Vec3) -> Vec3 {
+        a.x * self.u + a.y * self.v + a.z * self.w
+    }
+
+    // And back again
+    pub fn world_to_local(&self, a: Vec3) -> Vec3 {
+        Vec3::new(dot(&a, &self.u), dot(&a, &self.v), dot(&a, &self.w))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::vector::{cross, Vec3};